@@ -1,22 +1,65 @@
 use crate::game::*;
+use crate::moves::*;
+use crate::tt::*;
+use std::time::{Duration, Instant};
+
+/// 將死之分數：於第 ply 步（半回合）將死對方記爲 MATE_SCORE - ply
+pub const MATE_SCORE: i32 = 30000;
+
+/// 絕對值超過此值之分數表示將死
+pub const MATE_BOUND: i32 = MATE_SCORE - 1000;
+
+/// 大於任何分數之界限
+pub const INFINITE_SCORE: i32 = 32000;
 
 /// AI 決定
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct AIDecision {
-    pub step: Option<((i32, i32), (i32, i32))>,
-    pub score: f32,
+    pub step: Option<Move>,
+    /// 以走子方視角之分數（百分之一兵）
+    pub score: i32,
+    /// 主要變例，首著即 step
+    pub pv: Vec<Move>,
+    /// 已完成之搜索深度
+    pub depth: u32,
+    /// 搜索節點數
+    pub nodes: u64,
+    /// 用時
+    pub elapsed: Duration,
 }
 
 /// 估價器
 pub trait Evaluator {
-    /// 估價
-    fn evaluate(&self, board: &Board, side: Side) -> f32;
+    /// 以 side 方視角估價，單位爲百分之一兵（centipawn）
+    fn evaluate(&self, board: &Board, side: Side) -> i32;
+}
+
+/// 將死分數對應之回合數：正數爲走子方 n 回合內將死對方，負數爲走子方 n 回合內被將死
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score > MATE_BOUND {
+        Some((MATE_SCORE - score + 1) / 2)
+    } else if score < -MATE_BOUND {
+        Some(-(MATE_SCORE + score) / 2)
+    } else {
+        None
+    }
+}
+
+/// 將分數映射爲走子方之勝率估計（0..1），僅供顯示；調參另以擬合之邏輯函數計算
+pub fn win_probability(score: i32) -> f64 {
+    (score as f64 / 40000.0 + 0.5).clamp(0.0, 1.0)
 }
 
 /// 短視估價器——將所有棋子分數加起來
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ShortSightedEvaluator {}
 
+impl Default for ShortSightedEvaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ShortSightedEvaluator {
     pub fn new() -> Self {
         Self {}
@@ -72,9 +115,9 @@ impl ShortSightedEvaluator {
 }
 
 impl Evaluator for ShortSightedEvaluator {
-    fn evaluate(&self, board: &Board, side: Side) -> f32 {
+    fn evaluate(&self, board: &Board, side: Side) -> i32 {
         let mut score = 0;
-        if board.piece_count_of_board() <= 16 {
+        if board.piece_count_of_board() > 16 {
             for x in 0..9 {
                 for y in 0..10 {
                     score += Self::evaluate_single_piece_1(board, side, (x, y));
@@ -97,150 +140,382 @@ impl Evaluator for ShortSightedEvaluator {
         {
             score -= 10;
         }
-        score as f32 / 4000.0f32 + 0.5f32
+        score * 10
     }
 }
 
-/// 最大-最小算法之最大
-pub fn max_search<EvaluatorT>(
-    depth: u32,
-    current_node_count: &mut u32,
-    max_node_count: u32,
-    board: &mut Board,
-    side: Side,
-    evaluator: &EvaluatorT,
-    mut alpha: f32,
-    beta: f32,
-) -> Option<AIDecision>
+/// 對局已結束時以走子方視角之分數，愈早分出勝負絕對值愈大
+fn finished_score(board: &Board, ply: u32) -> i32 {
+    match board.get_winner() {
+        Some(winner) if winner == board.side_to_move() => MATE_SCORE - ply as i32,
+        Some(_) => -(MATE_SCORE - ply as i32),
+        None => 0,
+    }
+}
+
+/// 記錄殺手著法與主要變例之最大步數
+const MAX_PLY: usize = 128;
+
+// 著法排序分值：置換表著法最先，其次吃子（MVV-LVA），再次殺手著法，最後按歷史分值
+const TT_MOVE_ORDER: i32 = 1_000_000;
+const CAPTURE_ORDER: i32 = 100_000;
+const KILLER_ORDER: i32 = 90_000;
+
+// 用於 MVV-LVA 之棋子價值
+fn order_value(piece: Piece) -> i32 {
+    match piece {
+        Piece::兵 => 1,
+        Piece::仕 | Piece::相 => 2,
+        Piece::馬 | Piece::炮 => 4,
+        Piece::車 => 9,
+        Piece::帥 => 100,
+    }
+}
+
+/// 每搜索此數之節點檢查一次時間
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// 搜索器：持有估價器、置換表、著法排序表、主要變例與節點計數，
+/// 可跨逐層加深之各層重用
+pub struct Searcher<'a, EvaluatorT>
+where
+    EvaluatorT: Evaluator,
+{
+    evaluator: &'a EvaluatorT,
+    tt: &'a mut TranspositionTable,
+    /// 各步引起截斷之不吃子著法
+    killers: [[Option<Move>; 2]; MAX_PLY],
+    /// 不吃子著法引起截斷之累計分值，以起止點下標索引
+    history: Vec<[i32; 90]>,
+    /// 三角主要變例表：pv[ply] 爲自該步起之主要變例
+    pv: Vec<MoveList>,
+    /// 本層根節點最先搜索之著法
+    root_move: Option<Move>,
+    /// 本層根節點已確定之最佳分數
+    root_score: i32,
+    node_count: u64,
+    max_node_count: u64,
+    deadline: Option<Instant>,
+    stopped: bool,
+}
+
+impl<'a, EvaluatorT> Searcher<'a, EvaluatorT>
 where
     EvaluatorT: Evaluator,
 {
-    if *current_node_count > max_node_count {
-        return None;
+    /// 構建，搜索節點數超過 max_node_count 即中止
+    pub fn new(
+        evaluator: &'a EvaluatorT,
+        tt: &'a mut TranspositionTable,
+        max_node_count: u64,
+    ) -> Self {
+        Self {
+            evaluator,
+            tt,
+            killers: [[None; 2]; MAX_PLY],
+            history: vec![[0; 90]; 90],
+            pv: vec![MoveList::new(); MAX_PLY + 1],
+            root_move: None,
+            root_score: -INFINITE_SCORE,
+            node_count: 0,
+            max_node_count,
+            deadline: None,
+            stopped: false,
+        }
+    }
+
+    /// 設置中止搜索之時刻
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
     }
-    *current_node_count += 1;
-    if depth == 0 || board.finished() {
+
+    /// 已搜索之節點數
+    pub fn node_count(&self) -> u64 {
+        self.node_count
+    }
+
+    /// 搜索是否已因節點數或時間超限而中止，中止後不再搜索
+    pub fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    // 檢查節點數與時間，超限則中止
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped {
+            self.stopped = self.node_count > self.max_node_count
+                || self.node_count.is_multiple_of(TIME_CHECK_INTERVAL)
+                    && self
+                        .deadline
+                        .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+        self.stopped
+    }
+
+    /// 以固定深度搜索 board，返回最佳著法、分數與主要變例；first 爲根節點最先搜索之著法，
+    /// 逐層加深時應傳入上一層之最佳著法。中途中止時，若 first 已搜索完畢，
+    /// 則返回已搜完之著法中最佳者（不劣於 first），深度記爲 depth - 1；否則返回 None
+    pub fn search(
+        &mut self,
+        board: &mut Board,
+        depth: u32,
+        first: Option<Move>,
+    ) -> Option<AIDecision> {
+        let start = Instant::now();
+        let start_node_count = self.node_count;
+        self.root_move = first;
+        self.root_score = -INFINITE_SCORE;
+        self.pv[0].clear();
+        // 根節點首著必先於其他著法完成並提高 alpha，故 pv[0] 非空即 first 已搜完
+        let (score, depth) = match self.negamax(board, depth, 0, -INFINITE_SCORE, INFINITE_SCORE) {
+            Some(score) => (score, depth),
+            None if !self.pv[0].is_empty() => (self.root_score, depth - 1),
+            None => return None,
+        };
+        let pv: Vec<Move> = self.pv[0].iter().copied().collect();
         Some(AIDecision {
-            step: None,
-            score: evaluator.evaluate(board, side),
+            step: pv.first().copied(),
+            score,
+            pv,
+            depth,
+            nodes: self.node_count - start_node_count,
+            elapsed: start.elapsed(),
         })
-    } else {
-        let mut score = std::f32::NEG_INFINITY;
-        let mut step = ((0, 0), (0, 0));
-        for x in 0..9 {
-            for y in 0..10 {
-                let from = (x, y);
-                if !board.has_friend_at(side, from) {
-                    continue;
+    }
+
+    // 負極大值搜索（帶 alpha-beta 剪枝），ply 爲距根節點之步數；中止時返回 None
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        depth: u32,
+        ply: u32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Option<i32> {
+        if self.should_stop() {
+            return None;
+        }
+        self.node_count += 1;
+        if let Some(pv) = self.pv.get_mut(ply as usize) {
+            pv.clear();
+        }
+        let side = board.side_to_move();
+        if board.finished() {
+            return Some(finished_score(board, ply));
+        }
+        if depth == 0 {
+            return self.quiesce(board, ply, alpha, beta);
+        }
+        let key = board.hash();
+        let mut tt_move = None;
+        if let Some(entry) = self.tt.probe(key, ply) {
+            tt_move = entry.best_move;
+            // 根節點須給出著法，主要變例節點（窗口寬於 1）須給出完整之主要變例，
+            // 故均不以置換表截斷
+            if ply > 0 && beta - alpha == 1 && entry.depth >= depth {
+                let cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.score >= beta,
+                    Bound::Upper => entry.score <= alpha,
+                };
+                if cutoff {
+                    return Some(entry.score);
                 }
-                for to in board.all_possible_moves(from) {
-                    board.do_move_unchecked(from, to);
-                    let v = min_search(
-                        depth - 1,
-                        current_node_count,
-                        max_node_count,
-                        board,
-                        side,
-                        evaluator,
-                        alpha,
-                        beta,
-                    )?;
-                    if v.score > score {
-                        score = v.score;
-                        step = (from, to);
-                    }
-                    if score > alpha {
-                        alpha = score;
-                    }
-                    board.undo_move().unwrap();
-                    if alpha >= beta {
-                        break;
-                    }
+            }
+        }
+        if ply == 0 && self.root_move.is_some() {
+            // 置換表之根節點項可能已被覆蓋，上一層之最佳著法須明確先搜
+            tt_move = self.root_move;
+        }
+        let original_alpha = alpha;
+        let mut moves = board.legal_moves(side);
+        self.order_moves(board, &mut moves, ply, tt_move);
+        let mut score = -INFINITE_SCORE;
+        let mut step = None;
+        for mv in moves {
+            board.do_move_unchecked(mv);
+            let v = self.negamax(board, depth - 1, ply + 1, -beta, -alpha);
+            board.undo_move().unwrap();
+            let v = -v?;
+            if v > score {
+                score = v;
+                step = Some(mv);
+            }
+            if score > alpha {
+                alpha = score;
+                self.update_pv(ply, mv);
+                if ply == 0 {
+                    self.root_score = score;
                 }
             }
+            if alpha >= beta {
+                if !mv.is_capture() {
+                    self.record_cutoff(mv, ply, depth);
+                }
+                break;
+            }
         }
-        if score > std::f32::NEG_INFINITY {
-            Some(AIDecision {
-                step: Some(step),
-                score,
-            })
+        if step.is_some() {
+            let bound = if score <= original_alpha {
+                Bound::Upper
+            } else if score >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            self.tt.store(key, ply, depth, bound, score, step);
+            Some(score)
         } else {
-            Some(AIDecision {
-                step: None,
-                score: evaluator.evaluate(board, side),
-            })
+            Some(self.evaluator.evaluate(board, side))
         }
     }
-}
 
-/// 最大-最小算法之最小
-pub fn min_search<EvaluatorT>(
-    depth: u32,
-    current_node_count: &mut u32,
-    max_node_count: u32,
-    board: &mut Board,
-    side: Side,
-    evaluator: &EvaluatorT,
-    alpha: f32,
-    mut beta: f32,
-) -> Option<AIDecision>
-where
-    EvaluatorT: Evaluator,
-{
-    if *current_node_count > max_node_count {
-        return None;
+    /// 靜態搜索：僅搜吃子，以不吃子時之估價（stand-pat）爲下界；被將軍時則搜全部應將之著。
+    /// 中止時返回 None
+    pub fn quiesce(
+        &mut self,
+        board: &mut Board,
+        ply: u32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Option<i32> {
+        if self.should_stop() {
+            return None;
+        }
+        self.node_count += 1;
+        if board.finished() {
+            return Some(finished_score(board, ply));
+        }
+        let side = board.side_to_move();
+        let mut score = -INFINITE_SCORE;
+        let mut moves = if board.is_in_check(side) {
+            board.legal_moves(side)
+        } else {
+            score = self.evaluator.evaluate(board, side);
+            if score >= beta {
+                return Some(score);
+            }
+            alpha = alpha.max(score);
+            board.captures(side)
+        };
+        self.order_moves(board, &mut moves, ply, None);
+        for mv in moves {
+            board.do_move_unchecked(mv);
+            let v = self.quiesce(board, ply + 1, -beta, -alpha);
+            board.undo_move().unwrap();
+            let v = -v?;
+            if v > score {
+                score = v;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        if score == -INFINITE_SCORE {
+            // 被將軍而無著可應（僅見於未經 do_move 判定之局面）
+            score = self.evaluator.evaluate(board, side);
+        }
+        Some(score)
     }
-    *current_node_count += 1;
-    if depth == 0 || board.finished() {
-        Some(AIDecision {
-            step: None,
-            score: evaluator.evaluate(board, side),
-        })
-    } else {
-        let mut score = std::f32::INFINITY;
-        let mut step = ((0, 0), (0, 0));
-        for x in 0..9 {
-            for y in 0..10 {
-                let from = (x, y);
-                if !board.has_friend_at(side.other(), from) {
-                    continue;
-                }
-                for to in board.all_possible_moves(from) {
-                    board.do_move_unchecked(from, to);
-                    let v = max_search(
-                        depth - 1,
-                        current_node_count,
-                        max_node_count,
-                        board,
-                        side,
-                        evaluator,
-                        alpha,
-                        beta,
-                    )?;
-                    if v.score < score {
-                        score = v.score;
-                        step = (from, to);
-                    }
-                    if score < beta {
-                        beta = score;
-                    }
-                    board.undo_move().unwrap();
-                    if alpha >= beta {
-                        break;
-                    }
+
+    // 著法之排序分值，愈大愈先搜
+    fn order_score(&self, board: &Board, mv: &Move, ply: u32, tt_move: Option<Move>) -> i32 {
+        if tt_move.is_some_and(|tt_move| tt_move.same_squares(mv)) {
+            return TT_MOVE_ORDER;
+        }
+        if let Some(captured) = mv.captured {
+            let attacker = board
+                .piece_at(mv.from)
+                .map_or(0, |piece| order_value(piece.1));
+            return CAPTURE_ORDER + order_value(captured) * 100 - attacker;
+        }
+        if let Some(killers) = self.killers.get(ply as usize) {
+            for (index, killer) in killers.iter().enumerate() {
+                if killer.is_some_and(|killer| killer.same_squares(mv)) {
+                    return KILLER_ORDER - index as i32;
                 }
             }
         }
-        if score < std::f32::INFINITY {
-            Some(AIDecision {
-                step: Some(step),
-                score,
-            })
-        } else {
-            Some(AIDecision {
-                step: None,
-                score: evaluator.evaluate(board, side),
-            })
+        self.history[mv.from.index()][mv.to.index()]
+    }
+
+    // 按排序分值自大至小排列著法
+    fn order_moves(&self, board: &Board, moves: &mut MoveList, ply: u32, tt_move: Option<Move>) {
+        moves.sort_unstable_by_key(|mv| -self.order_score(board, mv, ply, tt_move));
+    }
+
+    // 以 mv 接續下一步之主要變例作爲本步之主要變例
+    fn update_pv(&mut self, ply: u32, mv: Move) {
+        let ply = ply as usize;
+        if ply >= MAX_PLY {
+            return;
+        }
+        let (current, rest) = self.pv.split_at_mut(ply + 1);
+        let pv = &mut current[ply];
+        pv.clear();
+        pv.push(mv);
+        for &next in rest[0].iter() {
+            pv.push(next);
+        }
+    }
+
+    // 記錄引起截斷之不吃子著法
+    fn record_cutoff(&mut self, mv: Move, ply: u32, depth: u32) {
+        if let Some(killers) = self.killers.get_mut(ply as usize) {
+            if !killers[0].is_some_and(|killer| killer.same_squares(&mv)) {
+                killers[1] = killers[0];
+                killers[0] = Some(mv);
+            }
         }
+        let history = &mut self.history[mv.from.index()][mv.to.index()];
+        *history = (*history + (depth * depth) as i32).min(KILLER_ORDER - 2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 以固定深度搜索 fen 局面
+    fn search(fen: &str, depth: u32) -> AIDecision {
+        let mut board = Board::from_fen(fen).unwrap();
+        let evaluator = ShortSightedEvaluator::new();
+        let mut tt = TranspositionTable::default();
+        let mut searcher = Searcher::new(&evaluator, &mut tt, u64::MAX);
+        searcher.search(&mut board, depth, None).unwrap()
+    }
+
+    #[test]
+    fn shortest_mate_is_preferred() {
+        // 一步即可將死，亦有更長之殺法
+        let decision = search("3k5/9/9/9/9/9/9/9/9/R3K3R w - - 0 1", 4);
+        assert_eq!(decision.score, MATE_SCORE - 1);
+        assert_eq!(mate_in(decision.score), Some(1));
+        assert_eq!(decision.pv.len(), 1);
+    }
+
+    #[test]
+    fn mate_score_counts_plies_to_mate() {
+        let fen = "2bak4/9/9/9/9/9/9/9/4A4/R2AK3R w - - 0 1";
+        let decision = search(fen, 5);
+        assert_eq!(decision.score, MATE_SCORE - 5);
+        assert_eq!(mate_in(decision.score), Some(3));
+        assert_eq!(decision.pv.len(), 5);
+        let mut board = Board::from_fen(fen).unwrap();
+        for &step in &decision.pv {
+            board.do_move(step).unwrap();
+        }
+        assert_eq!(board.termination(), Some(Termination::Checkmate));
+
+        // 被將死之一方
+        let mut board = Board::from_fen(fen).unwrap();
+        board.do_move(decision.pv[0]).unwrap();
+        let decision = search(&board.to_fen(), 4);
+        assert_eq!(decision.score, -(MATE_SCORE - 4));
+        assert_eq!(mate_in(decision.score), Some(-2));
+        assert_eq!(decision.pv.len(), 4);
     }
 }
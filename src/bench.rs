@@ -0,0 +1,42 @@
+use crate::ai::*;
+use crate::game::*;
+use crate::tt::*;
+use std::time::Instant;
+
+/// 基準測試所用局面（開局、中局、殘局）
+const BENCH_POSITIONS: [&str; 3] = [
+    START_FEN,
+    "r1ba1a3/4kn3/2n1b4/pNp1p1p1p/4c4/6P2/P1P2R2P/1CcC5/9/2BAKAB2 w - - 0 1",
+    "4ka3/4a4/9/9/4N4/p8/9/4C3c/7n1/2BK5 w - - 0 1",
+];
+
+/// 以固定深度對各局面運行 negamax，輸出節點數與每秒節點數
+pub fn run_benchmark(depth: u32) {
+    let evaluator = ShortSightedEvaluator::new();
+    let mut total_nodes: u64 = 0;
+    let start = Instant::now();
+    for fen in BENCH_POSITIONS {
+        let mut board = Board::from_fen(fen).unwrap();
+        let mut tt = TranspositionTable::default();
+        let mut searcher = Searcher::new(&evaluator, &mut tt, u64::MAX);
+        let position_start = Instant::now();
+        searcher.search(&mut board, depth, None);
+        let node_count = searcher.node_count();
+        let elapsed = position_start.elapsed().as_secs_f64();
+        println!(
+            "{}: {} 節點，{:.3} 秒，{:.0} 節點/秒",
+            fen,
+            node_count,
+            elapsed,
+            node_count as f64 / elapsed
+        );
+        total_nodes += node_count;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "合計：{} 節點，{:.3} 秒，{:.0} 節點/秒",
+        total_nodes,
+        elapsed,
+        total_nodes as f64 / elapsed
+    );
+}
@@ -0,0 +1,51 @@
+use crate::moves::*;
+
+/// 位棋盤：以 128 位整數之低 90 位表示棋盤上各位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Bitboard(u128);
+
+impl Bitboard {
+    /// 空位棋盤
+    pub const EMPTY: Bitboard = Bitboard(0);
+
+    /// 是否含某位置
+    pub fn contains(self, square: Square) -> bool {
+        self.0 >> square.index() & 1 != 0
+    }
+
+    /// 加入某位置
+    pub fn insert(&mut self, square: Square) {
+        self.0 |= 1 << square.index();
+    }
+
+    /// 移除某位置
+    pub fn remove(&mut self, square: Square) {
+        self.0 &= !(1 << square.index());
+    }
+
+    /// 是否爲空
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// 自低到高遍歷所含位置
+    pub fn squares(self) -> impl Iterator<Item = Square> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let index = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            Square::from_index(index)
+        })
+    }
+}
+
+impl std::ops::BitOr for Bitboard {
+    type Output = Bitboard;
+
+    fn bitor(self, other: Bitboard) -> Bitboard {
+        Bitboard(self.0 | other.0)
+    }
+}
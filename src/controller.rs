@@ -1,16 +1,33 @@
 use crate::ai::*;
 use crate::game::*;
+use crate::limits::*;
+use crate::moves::*;
+use crate::tt::*;
 use rand::prelude::*;
+use std::cell::RefCell;
 
 /// 控制器
 pub trait Controller {
-    fn decide(&self, side: Side, board: &Board) -> Option<((i32, i32), (i32, i32))>;
+    fn decide(&self, board: &Board) -> Option<Move>;
+
+    /// 決定並走出一著
+    fn play(&self, board: &mut Board) -> Result<Move, MoveError> {
+        let step = self.decide(board).ok_or(MoveError::GameFinished)?;
+        board.do_move(step)?;
+        Ok(step)
+    }
 }
 
 /// 隨機走子控制器
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RandomController {}
 
+impl Default for RandomController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RandomController {
     /// 構建
     pub fn new() -> RandomController {
@@ -19,51 +36,114 @@ impl RandomController {
 }
 
 impl Controller for RandomController {
-    fn decide(&self, side: Side, board: &Board) -> Option<((i32, i32), (i32, i32))> {
-        let mut froms: Vec<(i32, i32)> = Vec::new();
-        for x in 0..9 {
-            for y in 0..10 {
-                let pos: (i32, i32) = (x, y);
-                if !board.has_friend_at(side, pos) {
-                    continue;
-                }
-                let possible_moves = board.all_possible_moves(pos);
-                if !possible_moves.is_empty() {
-                    froms.push(pos);
-                }
-            }
-        }
-        if froms.is_empty() {
-            None
-        } else {
-            let mut rng = rand::thread_rng();
-            froms.shuffle(&mut rng);
-            let from = froms.first().unwrap().clone();
-            let mut tos = board.all_possible_moves(from);
-            tos.shuffle(&mut rng);
-            let to = tos.first().unwrap().clone();
-            Some((from, to))
-        }
+    fn decide(&self, board: &Board) -> Option<Move> {
+        let moves = board.legal_moves(board.side_to_move());
+        moves.choose(&mut rand::thread_rng()).copied()
     }
 }
 
+/// 逐層加深之最大深度
+const MAX_DEPTH: u32 = 99;
+
+/// 搜索控制器，置換表跨逐層加深與跨著法保留
 pub struct AIController<EvaluatorT>
 where
     EvaluatorT: Evaluator,
 {
     evaluator: EvaluatorT,
-    max_node_count: u32,
+    limits: SearchLimits,
+    tt: RefCell<TranspositionTable>,
 }
 
 impl<EvaluatorT> AIController<EvaluatorT>
 where
     EvaluatorT: Evaluator,
 {
-    pub fn new(evaluator: EvaluatorT, max_node_count: u32) -> Self {
+    /// 構建，每步搜索至多 max_node_count 個節點，置換表取默認大小
+    pub fn new(evaluator: EvaluatorT, max_node_count: u64) -> Self {
         Self {
             evaluator,
-            max_node_count,
+            limits: SearchLimits::nodes(max_node_count),
+            tt: RefCell::new(TranspositionTable::default()),
+        }
+    }
+
+    /// 設置每步之搜索限制
+    pub fn with_limits(mut self, limits: SearchLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// 設置置換表大小（MB），原有內容清空
+    pub fn with_tt_size(mut self, megabytes: usize) -> Self {
+        self.tt = RefCell::new(TranspositionTable::new(megabytes));
+        self
+    }
+
+    /// 清空置換表（如開始新對局時）
+    pub fn clear_tt(&self) {
+        self.tt.borrow_mut().clear();
+    }
+
+    /// 按所設搜索限制思考
+    pub fn think(&self, board: &Board) -> Option<AIDecision> {
+        self.think_with_limits(board, &self.limits)
+    }
+
+    /// 逐層加深搜索，直至達到限制或時間管理停止加深；首層不受時限約束，總會搜完。
+    /// 返回最後一層之決定（末層未完成時取其已搜完之著法中最佳者）；
+    /// 節點數與用時計入全部各層。對局已結束則返回 None
+    pub fn think_with_limits(&self, board: &Board, limits: &SearchLimits) -> Option<AIDecision> {
+        if board.finished() {
+            return None;
+        }
+        let time_manager = TimeManager::new(limits);
+        let mut decision: Option<AIDecision> = None;
+        let mut tt = self.tt.borrow_mut();
+        let mut searcher =
+            Searcher::new(&self.evaluator, &mut tt, limits.nodes.unwrap_or(u64::MAX));
+        let mut mboard = board.clone();
+        let max_depth = limits.depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH);
+        // 淺層結果經置換表指導更深之搜索；上一層之最佳著法先搜，
+        // 未完成之一層僅在其已搜完時方返回結果，所得之最佳著法不劣於上一層
+        for depth in 1..=max_depth {
+            let first = decision.as_ref().and_then(|decision| decision.step);
+            let result = match searcher.search(&mut mboard, depth, first) {
+                Some(result) if result.step.is_some() => result,
+                _ => break,
+            };
+            // 已找到將死，更深之搜索不會更短
+            let mate_found = mate_in(result.score).is_some();
+            decision = Some(result);
+            if searcher.stopped() || mate_found || !time_manager.should_deepen() {
+                break;
+            }
+            // 首層不受時限約束，時間將盡時亦能給出經搜索之著法
+            if depth == 1 {
+                if let Some(deadline) = time_manager.deadline() {
+                    searcher = searcher.with_deadline(deadline);
+                }
+            }
         }
+        let nodes = searcher.node_count();
+        // 節點數不足以搜完首層一著時，仍須給出著法
+        let decision = decision.or_else(|| {
+            let side = board.side_to_move();
+            let step = *board.legal_moves(side).first()?;
+            Some(AIDecision {
+                step: Some(step),
+                score: self.evaluator.evaluate(board, side),
+                pv: vec![step],
+                depth: 0,
+                nodes: 0,
+                elapsed: Default::default(),
+            })
+        })?;
+        Some(AIDecision {
+            nodes,
+            elapsed: time_manager.elapsed(),
+            ..decision
+        })
     }
 }
 
@@ -71,47 +151,49 @@ impl<EvaluatorT> Controller for AIController<EvaluatorT>
 where
     EvaluatorT: Evaluator,
 {
-    fn decide(&self, side: Side, board: &Board) -> Option<((i32, i32), (i32, i32))> {
-        let mut mboard = board.clone();
-        let mut current_node_count = 0;
-        let mut step = max_search(
-            1,
-            &mut current_node_count,
-            self.max_node_count,
-            &mut mboard,
-            side,
-            &self.evaluator,
-            std::f32::NEG_INFINITY,
-            std::f32::INFINITY,
-        );
-        match step {
-            Some(_) => {
-                for depth in 0..100 {
-                    mboard = board.clone();
-                    current_node_count = 0;
-                    let nstep = max_search(
-                        depth,
-                        &mut current_node_count,
-                        self.max_node_count,
-                        &mut mboard,
-                        side,
-                        &self.evaluator,
-                        std::f32::NEG_INFINITY,
-                        std::f32::INFINITY,
-                    );
-                    match nstep {
-                        Some(_) => {
-                            step = nstep;
-                            continue;
-                        }
-                        None => {
-                            break;
-                        }
-                    }
-                }
-                Some(step.unwrap().step.unwrap().clone())
+    fn decide(&self, board: &Board) -> Option<Move> {
+        self.think(board).and_then(|decision| decision.step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 紅方三回合內將死黑方
+    const MATE_IN_THREE: &str = "2bak4/9/9/9/9/9/9/9/4A4/R2AK3R w - - 0 1";
+
+    #[test]
+    fn pv_stays_complete_when_tt_is_reused() {
+        let board = Board::from_fen(MATE_IN_THREE).unwrap();
+        let controller = AIController::new(ShortSightedEvaluator::new(), u64::MAX)
+            .with_limits(SearchLimits::depth(5));
+        // 第二次思考時，主要變例上之節點均已存於置換表
+        for _ in 0..2 {
+            let decision = controller.think(&board).unwrap();
+            assert_eq!(mate_in(decision.score), Some(3));
+            assert_eq!(decision.pv.len() as i32, MATE_SCORE - decision.score);
+            let mut mboard = board.clone();
+            for &step in &decision.pv {
+                mboard.do_move(step).unwrap();
             }
-            None => None,
+            assert_eq!(mboard.result(), Some(GameResult::RedWins));
         }
     }
+
+    #[test]
+    fn first_depth_completes_when_clock_runs_out() {
+        let board = Board::new();
+        let controller = AIController::new(ShortSightedEvaluator::new(), u64::MAX);
+        // 剩餘時間不足預留之延遲，截止時刻於開始搜索時已過
+        let limits = SearchLimits::clock(Clock {
+            remaining: Duration::from_millis(10),
+            increment: Duration::ZERO,
+            moves_to_go: None,
+        });
+        let decision = controller.think_with_limits(&board, &limits).unwrap();
+        assert_eq!(decision.depth, 1);
+        assert!(decision.nodes > 1);
+    }
 }
@@ -1,5 +1,10 @@
+use crate::bitboard::*;
+use crate::moves::*;
+use crate::rules::*;
+use crate::zobrist;
 use ansi_term::Color;
 use ansi_term::Style;
+use std::fmt;
 
 /// 陣營
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -30,6 +35,184 @@ pub enum Piece {
     帥 = 7,
 }
 
+/// 對局結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    RedWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    /// 某方獲勝之結果
+    pub fn win_for(side: Side) -> GameResult {
+        match side {
+            Side::Red => GameResult::RedWins,
+            Side::Black => GameResult::BlackWins,
+        }
+    }
+
+    /// 獲取贏家，和棋則爲 None
+    pub fn winner(&self) -> Option<Side> {
+        match self {
+            GameResult::RedWins => Some(Side::Red),
+            GameResult::BlackWins => Some(Side::Black),
+            GameResult::Draw => None,
+        }
+    }
+}
+
+/// 終局原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// 將死
+    Checkmate,
+    /// 困斃
+    Stalemate,
+    /// 帥被吃
+    GeneralCaptured,
+    /// 自然限著（連續多步未吃子）
+    MoveLimit,
+    /// 重複局面
+    Repetition,
+    /// 議和
+    Agreement,
+    /// 認輸
+    Resignation,
+    /// 超時
+    Timeout,
+}
+
+/// 走子錯誤
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// 起點無棋子
+    NoPiece(Square),
+    /// 所走棋子不屬於當前走子方
+    NotYourTurn { side_to_move: Side },
+    /// 該棋子不能走到終點
+    IllegalDestination(Move),
+    /// 走後己方帥被將軍
+    LeavesGeneralInCheck(Move),
+    /// 對局已結束
+    GameFinished,
+    /// 無可悔之著
+    NothingToUndo,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoPiece(square) => write!(
+                f,
+                "起點 {} 無棋子 (no piece on from-square {})",
+                square, square
+            ),
+            MoveError::NotYourTurn { side_to_move } => write!(
+                f,
+                "現應{}方走子 (it is {:?}'s turn to move)",
+                match side_to_move {
+                    Side::Red => "紅",
+                    Side::Black => "黑",
+                },
+                side_to_move
+            ),
+            MoveError::IllegalDestination(step) => write!(
+                f,
+                "不能自 {} 走到 {} (illegal destination {} for the piece on {})",
+                step.from, step.to, step.to, step.from
+            ),
+            MoveError::LeavesGeneralInCheck(step) => write!(
+                f,
+                "自 {} 走到 {} 會送將 ({} -> {} would leave the general in check)",
+                step.from, step.to, step.from, step.to
+            ),
+            MoveError::GameFinished => write!(f, "對局已結束 (the game is already finished)"),
+            MoveError::NothingToUndo => write!(f, "無可悔之著 (there is no move to undo)"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// 標準開局之 FEN
+pub const START_FEN: &str = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+
+/// FEN 解析錯誤
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    /// 缺少局面字段
+    Empty,
+    /// 行數不爲十
+    WrongRankCount(usize),
+    /// 某行格數不爲九（行號自紅方底線 0 起算）
+    WrongFileCount(usize),
+    /// 無法識別之棋子字符
+    InvalidPiece(char),
+    /// 無法識別之走子方
+    InvalidSide(String),
+    /// 無法識別之步數
+    InvalidNumber(String),
+    /// 某方帥數目不爲一或不在九宮
+    InvalidGeneral(Side),
+    /// 非走子方正被將軍
+    NotToMoveInCheck(Side),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::Empty => write!(f, "FEN 爲空"),
+            FenError::WrongRankCount(count) => write!(f, "FEN 應有 10 行，實有 {} 行", count),
+            FenError::WrongFileCount(rank) => write!(f, "FEN 第 {} 行格數不爲 9", rank),
+            FenError::InvalidPiece(c) => write!(f, "FEN 含無法識別之棋子 '{}'", c),
+            FenError::InvalidSide(side) => write!(f, "FEN 含無法識別之走子方 '{}'", side),
+            FenError::InvalidNumber(number) => write!(f, "FEN 含無法識別之步數 '{}'", number),
+            FenError::InvalidGeneral(side) => write!(f, "FEN 中{:?}方帥之數目或位置有誤", side),
+            FenError::NotToMoveInCheck(side) => write!(f, "FEN 中非走子方{:?}方正被將軍", side),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+/// 棋子對應之 FEN 字符（紅方大寫，黑方小寫）
+fn piece_to_fen_char(piece: (Side, Piece)) -> char {
+    let c = match piece.1 {
+        Piece::兵 => 'p',
+        Piece::仕 => 'a',
+        Piece::相 => 'b',
+        Piece::炮 => 'c',
+        Piece::馬 => 'n',
+        Piece::車 => 'r',
+        Piece::帥 => 'k',
+    };
+    match piece.0 {
+        Side::Red => c.to_ascii_uppercase(),
+        Side::Black => c,
+    }
+}
+
+/// FEN 字符對應之棋子（兼容以 e、h 表示相、馬之寫法）
+fn piece_from_fen_char(c: char) -> Option<(Side, Piece)> {
+    let side = if c.is_ascii_uppercase() {
+        Side::Red
+    } else {
+        Side::Black
+    };
+    let piece = match c.to_ascii_lowercase() {
+        'p' => Piece::兵,
+        'a' => Piece::仕,
+        'b' | 'e' => Piece::相,
+        'c' => Piece::炮,
+        'n' | 'h' => Piece::馬,
+        'r' => Piece::車,
+        'k' => Piece::帥,
+        _ => return None,
+    };
+    Some((side, piece))
+}
+
 /// 獲取某處棋子相對編號（己方爲正，對方爲負）
 pub fn piece_relative_id(side: Side, piece: Option<(Side, Piece)>) -> i32 {
     match piece {
@@ -48,10 +231,13 @@ pub fn piece_relative_id(side: Side, piece: Option<(Side, Piece)>) -> i32 {
 /// 記錄走子歷史，以便悔棋
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct UndoMoveRecord {
-    from_pos: (i32, i32),
+    step: Move,
     from_piece: Option<(Side, Piece)>,
-    to_pos: (i32, i32),
     to_piece: Option<(Side, Piece)>,
+    outcome: Option<(GameResult, Termination)>,
+    halfmove_clock: u32,
+    /// 走子前局面之 Zobrist 鍵
+    hash: u64,
 }
 
 /// 檢查某位置是否在給定範圍內
@@ -65,14 +251,141 @@ fn position_inside_board(pos: (i32, i32)) -> bool {
     position_inside(pos, (0, 0), (8, 9))
 }
 
+/// 馬之八個走法（蹩腳位置爲其一半，向零取整）
+const HORSE_STEPS: [(i32, i32); 8] = [
+    (1, 2),
+    (-1, 2),
+    (1, -2),
+    (-1, -2),
+    (2, 1),
+    (-2, 1),
+    (2, -1),
+    (-2, -1),
+];
+
+/// 第 y 行對 side 方而言是否已過河
+fn crossed_river(side: Side, y: i32) -> bool {
+    match side {
+        Side::Red => y >= 5,
+        Side::Black => y <= 4,
+    }
+}
+
+/// 某位置是否在 side 方九宮內
+fn in_palace(side: Side, pos: (i32, i32)) -> bool {
+    match side {
+        Side::Red => position_inside(pos, (3, 0), (5, 2)),
+        Side::Black => position_inside(pos, (3, 7), (5, 9)),
+    }
+}
+
+/// 檢查地圖上 side 方之帥是否被將軍，facing_is_check 則帥帥照面亦算將軍
+fn map_in_check(map: &[[Option<(Side, Piece)>; 10]; 9], side: Side, facing_is_check: bool) -> bool {
+    let (left_down, right_up) = match side {
+        Side::Red => ((3, 0), (5, 2)),
+        Side::Black => ((3, 7), (5, 9)),
+    };
+    let mut king = None;
+    for x in left_down.0..(right_up.0 + 1) {
+        for y in left_down.1..(right_up.1 + 1) {
+            if map[x as usize][y as usize] == Some((side, Piece::帥)) {
+                king = Some((x, y));
+            }
+        }
+    }
+    match king {
+        Some(king) => map_king_attacked(map, king, side, facing_is_check),
+        None => false,
+    }
+}
+
+/// 檢查地圖上位於 king 之 side 方帥是否被將軍，facing_is_check 則帥帥照面亦算將軍
+fn map_king_attacked(
+    map: &[[Option<(Side, Piece)>; 10]; 9],
+    king: (i32, i32),
+    side: Side,
+    facing_is_check: bool,
+) -> bool {
+    let enemy = side.other();
+    let at = |pos: (i32, i32)| -> Option<(Side, Piece)> {
+        if position_inside_board(pos) {
+            map[pos.0 as usize][pos.1 as usize]
+        } else {
+            None
+        }
+    };
+
+    // 車、炮、帥帥照面
+    for dir in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+        let mut pos = (king.0 + dir.0, king.1 + dir.1);
+        let mut screened = false;
+        while position_inside_board(pos) {
+            if let Some(piece) = at(pos) {
+                if screened {
+                    if piece == (enemy, Piece::炮) {
+                        return true;
+                    }
+                    break;
+                }
+                if piece == (enemy, Piece::車)
+                    || facing_is_check && dir.0 == 0 && piece == (enemy, Piece::帥)
+                {
+                    return true;
+                }
+                screened = true;
+            }
+            pos = (pos.0 + dir.0, pos.1 + dir.1);
+        }
+    }
+
+    // 馬（蹩腳位置緊鄰馬）
+    for (dx, dy) in HORSE_STEPS {
+        let horse = (king.0 - dx, king.1 - dy);
+        if at(horse) != Some((enemy, Piece::馬)) {
+            continue;
+        }
+        let check = if dx.abs() == 1 {
+            (horse.0, horse.1 + dy / 2)
+        } else {
+            (horse.0 + dx / 2, horse.1)
+        };
+        if at(check).is_none() {
+            return true;
+        }
+    }
+
+    // 兵（帥在九宮內，橫向相鄰之敵兵必已過河）
+    let forward = match enemy {
+        Side::Red => 1,
+        Side::Black => -1,
+    };
+    at((king.0, king.1 - forward)) == Some((enemy, Piece::兵))
+        || at((king.0 + 1, king.1)) == Some((enemy, Piece::兵))
+        || at((king.0 - 1, king.1)) == Some((enemy, Piece::兵))
+}
+
 #[derive(Debug, Clone)]
 pub struct Board {
     map: [[Option<(Side, Piece)>; 10]; 9],
     undo_move_records: Vec<UndoMoveRecord>,
     move_count: u32,
-    game_finished: bool,
-    winner: Option<Side>,
+    outcome: Option<(GameResult, Termination)>,
     sum_piece: i32,
+    side_to_move: Side,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    hash: u64,
+    rules: Rules,
+    /// 雙方棋子所在位置（以 Side 爲下標）
+    occupancy: [Bitboard; 2],
+    /// 雙方帥之位置
+    kings: [Option<Square>; 2],
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Board {
@@ -113,386 +426,368 @@ impl Board {
         map[6][6] = Some((Side::Black, Piece::兵));
         map[8][6] = Some((Side::Black, Piece::兵));
 
-        Board {
-            map: map,
+        let mut board = Board {
+            map,
             undo_move_records: Vec::new(),
             move_count: 0,
-            game_finished: false,
-            winner: None,
-            sum_piece: 0,
-        }
-    }
-
-    /// 獲取 from 處棋子可到達之所有位置
-    pub fn all_possible_moves(&self, from: (i32, i32)) -> Vec<(i32, i32)> {
-        let fpiece = self.map[from.0 as usize][from.1 as usize];
-        match fpiece {
-            Some(fpiece) => {
-                let mut ret = Vec::<(i32, i32)>::new();
-                let side = fpiece.0;
-                match fpiece.1 {
-                    Piece::兵 => match side {
-                        Side::Red => {
-                            let pos = (from.0, from.1 + 1);
-                            if position_inside_board(pos) && !self.has_friend_at(side, pos) {
-                                ret.push(pos);
-                            }
-                            if position_inside(from, (0, 5), (8, 9)) {
-                                let pos = (from.0 + 1, from.1);
-                                if position_inside_board(pos) && !self.has_friend_at(side, pos) {
-                                    ret.push(pos);
-                                }
-                                let pos = (from.0 - 1, from.1);
-                                if position_inside_board(pos) && !self.has_friend_at(side, pos) {
-                                    ret.push(pos);
-                                }
-                            }
-                        }
-                        Side::Black => {
-                            let pos = (from.0, from.1 - 1);
-                            if position_inside_board(pos) && !self.has_friend_at(side, pos) {
-                                ret.push(pos);
-                            }
-                            if position_inside(from, (0, 0), (8, 4)) {
-                                let pos = (from.0 + 1, from.1);
-                                if position_inside_board(pos) && !self.has_friend_at(side, pos) {
-                                    ret.push(pos);
-                                }
-                                let pos = (from.0 - 1, from.1);
-                                if position_inside_board(pos) && !self.has_friend_at(side, pos) {
-                                    ret.push(pos);
-                                }
-                            }
-                        }
-                    },
-                    Piece::仕 => {
-                        let left_down = match side {
-                            Side::Red => (3, 0),
-                            Side::Black => (3, 7),
-                        };
-                        let right_up = match side {
-                            Side::Red => (5, 2),
-                            Side::Black => (5, 9),
-                        };
-                        let pos = (from.0 + 1, from.1 + 1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 + 1, from.1 - 1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 - 1, from.1 + 1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 - 1, from.1 - 1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
+            outcome: None,
+            sum_piece: 32,
+            side_to_move: Side::Red,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: 0,
+            rules: Rules::default(),
+            occupancy: [Bitboard::EMPTY; 2],
+            kings: [None; 2],
+        };
+        board.rebuild_piece_sets();
+        board.hash = board.compute_hash();
+        board
+    }
+
+    /// 以給定規則、默認開局初始化棋盤
+    pub fn with_rules(rules: Rules) -> Board {
+        let mut board = Board::new();
+        board.rules = rules;
+        board
+    }
+
+    /// 自 FEN 構建棋盤
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        Board::from_fen_with_rules(fen, Rules::default())
+    }
+
+    /// 以給定規則自 FEN 構建棋盤
+    pub fn from_fen_with_rules(fen: &str, rules: Rules) -> Result<Board, FenError> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next().ok_or(FenError::Empty)?;
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 10 {
+            return Err(FenError::WrongRankCount(ranks.len()));
+        }
+        let mut map: [[Option<(Side, Piece)>; 10]; 9] = [[None; 10]; 9];
+        let mut sum_piece = 0;
+        for (i, rank) in ranks.iter().enumerate() {
+            let y = 9 - i;
+            let mut x = 0;
+            for c in rank.chars() {
+                // 空格數爲 1–9，0 按無法識別之字符處理
+                if let Some(skip) = c.to_digit(10).filter(|skip| *skip > 0) {
+                    x += skip as usize;
+                } else {
+                    let piece = piece_from_fen_char(c).ok_or(FenError::InvalidPiece(c))?;
+                    if x >= 9 {
+                        return Err(FenError::WrongFileCount(y));
                     }
-                    Piece::相 => {
-                        let left_down = match side {
-                            Side::Red => (0, 0),
-                            Side::Black => (0, 5),
-                        };
-                        let right_up = match side {
-                            Side::Red => (8, 4),
-                            Side::Black => (8, 9),
-                        };
-                        let pos = (from.0 + 2, from.1 + 2);
-                        let check = (from.0 + 1, from.1 + 1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 + 2, from.1 - 2);
-                        let check = (from.0 + 1, from.1 - 1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 - 2, from.1 + 2);
-                        let check = (from.0 - 1, from.1 + 1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 - 2, from.1 - 2);
-                        let check = (from.0 - 1, from.1 - 1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
+                    map[x][y] = Some(piece);
+                    sum_piece += 1;
+                    x += 1;
+                }
+                if x > 9 {
+                    return Err(FenError::WrongFileCount(y));
+                }
+            }
+            if x != 9 {
+                return Err(FenError::WrongFileCount(y));
+            }
+        }
+        for side in [Side::Red, Side::Black] {
+            let palace = match side {
+                Side::Red => ((3, 0), (5, 2)),
+                Side::Black => ((3, 7), (5, 9)),
+            };
+            let mut count = 0;
+            for x in 0..9 {
+                for y in 0..10 {
+                    if map[x as usize][y as usize] == Some((side, Piece::帥)) {
+                        if !position_inside((x, y), palace.0, palace.1) {
+                            return Err(FenError::InvalidGeneral(side));
                         }
+                        count += 1;
                     }
-                    Piece::炮 => {
-                        for x in 0..(from.0) {
-                            let pos = (x, from.1);
-                            if !self.has_friend_at(side, pos) {
-                                if self.has_piece_at(pos) {
-                                    if self.piece_count(pos, from) == 3 {
-                                        ret.push(pos);
-                                    }
-                                } else {
-                                    if self.piece_count(pos, from) == 1 {
-                                        ret.push(pos);
-                                    }
-                                }
-                            }
-                        }
-                        for x in (from.0 + 1)..9 {
-                            let pos = (x, from.1);
-                            if !self.has_friend_at(side, pos) {
-                                if self.has_piece_at(pos) {
-                                    if self.piece_count(from, pos) == 3 {
-                                        ret.push(pos);
-                                    }
-                                } else {
-                                    if self.piece_count(from, pos) == 1 {
-                                        ret.push(pos);
-                                    }
-                                }
-                            }
-                        }
-                        for y in 0..(from.1) {
-                            let pos = (from.0, y);
-                            if !self.has_friend_at(side, pos) {
-                                if self.has_piece_at(pos) {
-                                    if self.piece_count(pos, from) == 3 {
-                                        ret.push(pos);
-                                    }
-                                } else {
-                                    if self.piece_count(pos, from) == 1 {
-                                        ret.push(pos);
-                                    }
-                                }
-                            }
-                        }
-                        for y in (from.1 + 1)..10 {
-                            let pos = (from.0, y);
-                            if !self.has_friend_at(side, pos) {
-                                if self.has_piece_at(pos) {
-                                    if self.piece_count(from, pos) == 3 {
-                                        ret.push(pos);
-                                    }
-                                } else {
-                                    if self.piece_count(from, pos) == 1 {
-                                        ret.push(pos);
-                                    }
-                                }
-                            }
+                }
+            }
+            if count != 1 {
+                return Err(FenError::InvalidGeneral(side));
+            }
+        }
+        let side_to_move = match fields.next() {
+            None | Some("w") | Some("r") => Side::Red,
+            Some("b") => Side::Black,
+            Some(side) => return Err(FenError::InvalidSide(side.to_string())),
+        };
+        if map_in_check(&map, side_to_move.other(), !rules.flying_general_capture) {
+            return Err(FenError::NotToMoveInCheck(side_to_move.other()));
+        }
+        // 第三、四字段在象棋中恆爲 "-"
+        fields.next();
+        fields.next();
+        let parse_number = |field: Option<&str>, default: u32| match field {
+            Some(number) => number
+                .parse::<u32>()
+                .map_err(|_| FenError::InvalidNumber(number.to_string())),
+            None => Ok(default),
+        };
+        let halfmove_clock = parse_number(fields.next(), 0)?;
+        let fullmove_number = parse_number(fields.next(), 1)?.max(1);
+
+        let mut board = Board {
+            map,
+            undo_move_records: Vec::new(),
+            move_count: 0,
+            outcome: None,
+            sum_piece,
+            side_to_move,
+            halfmove_clock,
+            fullmove_number,
+            hash: 0,
+            rules,
+            occupancy: [Bitboard::EMPTY; 2],
+            kings: [None; 2],
+        };
+        board.rebuild_piece_sets();
+        board.hash = board.compute_hash();
+        board.finish_if_no_legal_move();
+        Ok(board)
+    }
+
+    /// 導出 FEN
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for y in (0..10).rev() {
+            let mut empty = 0;
+            for x in 0..9 {
+                match self.map[x][y] {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
                         }
+                        fen.push(piece_to_fen_char(piece));
                     }
-                    Piece::馬 => {
-                        let pos = (from.0 + 1, from.1 + 2);
-                        let check = (from.0, from.1 + 1);
-                        if position_inside_board(pos)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 + 1, from.1 - 2);
-                        let check = (from.0, from.1 - 1);
-                        if position_inside_board(pos)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 - 1, from.1 + 2);
-                        let check = (from.0, from.1 + 1);
-                        if position_inside_board(pos)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 - 1, from.1 - 2);
-                        let check = (from.0, from.1 - 1);
-                        if position_inside_board(pos)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 + 2, from.1 + 1);
-                        let check = (from.0 + 1, from.1);
-                        if position_inside_board(pos)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 + 2, from.1 - 1);
-                        let check = (from.0 + 1, from.1);
-                        if position_inside_board(pos)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 - 2, from.1 + 1);
-                        let check = (from.0 - 1, from.1);
-                        if position_inside_board(pos)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0 - 2, from.1 - 1);
-                        let check = (from.0 - 1, from.1);
-                        if position_inside_board(pos)
-                            && !self.has_piece_at(check)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
+                    None => {
+                        empty += 1;
                     }
-                    Piece::車 => {
-                        for x in 0..(from.0) {
-                            let pos = (x, from.1);
-                            if !self.has_friend_at(side, pos) {
-                                if self.has_enemy_at(side, pos) {
-                                    if self.piece_count(pos, from) == 2 {
-                                        ret.push(pos);
-                                    }
-                                } else {
-                                    if self.piece_count(pos, from) == 1 {
-                                        ret.push(pos);
-                                    }
-                                }
-                            }
-                        }
-                        for x in (from.0 + 1)..9 {
-                            let pos = (x, from.1);
-                            if !self.has_friend_at(side, pos) {
-                                if self.has_enemy_at(side, pos) {
-                                    if self.piece_count(from, pos) == 2 {
-                                        ret.push(pos);
-                                    }
-                                } else {
-                                    if self.piece_count(from, pos) == 1 {
-                                        ret.push(pos);
-                                    }
-                                }
-                            }
-                        }
-                        for y in 0..(from.1) {
-                            let pos = (from.0, y);
-                            if !self.has_friend_at(side, pos) {
-                                if self.has_enemy_at(side, pos) {
-                                    if self.piece_count(pos, from) == 2 {
-                                        ret.push(pos);
-                                    }
-                                } else {
-                                    if self.piece_count(pos, from) == 1 {
-                                        ret.push(pos);
-                                    }
-                                }
-                            }
-                        }
-                        for y in (from.1 + 1)..10 {
-                            let pos = (from.0, y);
-                            if !self.has_friend_at(side, pos) {
-                                if self.has_enemy_at(side, pos) {
-                                    if self.piece_count(from, pos) == 2 {
-                                        ret.push(pos);
-                                    }
-                                } else {
-                                    if self.piece_count(from, pos) == 1 {
-                                        ret.push(pos);
-                                    }
-                                }
-                            }
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if y > 0 {
+                fen.push('/');
+            }
+        }
+        let side = match self.side_to_move {
+            Side::Red => 'w',
+            Side::Black => 'b',
+        };
+        format!(
+            "{} {} - - {} {}",
+            fen, side, self.halfmove_clock, self.fullmove_number
+        )
+    }
+
+    /// 獲取 from 處棋子之所有著法（已排除送將之著）
+    ///
+    /// 因長將、長捉而判負之著仍屬合法，走後對局以判負結束。
+    pub fn all_possible_moves(&self, from: Square) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.generate_moves(from, false, |step| moves.push(step));
+        moves
+    }
+
+    /// 獲取 side 方供搜索之著法（已排除送將之著，並排除因長將、長捉而判負之著，
+    /// 除非全部著法均判負）
+    pub fn legal_moves(&self, side: Side) -> MoveList {
+        let mut moves = MoveList::new();
+        let mut losing = MoveList::new();
+        for from in self.occupancy[side as usize].squares() {
+            self.generate_moves(from, false, |step| {
+                if self.loses_by_repetition(step) {
+                    losing.push(step);
+                } else {
+                    moves.push(step);
+                }
+            });
+        }
+        if moves.is_empty() {
+            losing
+        } else {
+            moves
+        }
+    }
+
+    /// 獲取 side 方之所有吃子著法（已排除送將之著）
+    pub fn captures(&self, side: Side) -> MoveList {
+        let mut moves = MoveList::new();
+        for from in self.occupancy[side as usize].squares() {
+            self.generate_moves(from, true, |step| moves.push(step));
+        }
+        moves
+    }
+
+    // 對 from 處棋子不送將之每一著調用 visit，captures_only 則僅限吃子
+    fn generate_moves(&self, from: Square, captures_only: bool, mut visit: impl FnMut(Move)) {
+        let (side, piece) = match self.piece_at(from) {
+            Some(piece) => piece,
+            None => return,
+        };
+        self.for_each_pseudo_target(from, |to| {
+            let captured = self.piece_at(to).map(|target| target.1);
+            if captures_only && captured.is_none() {
+                return;
+            }
+            let flying = piece == Piece::帥 && captured == Some(Piece::帥);
+            if flying && !self.rules.flying_general_capture {
+                return;
+            }
+            if self.leaves_in_check(side, from, to) {
+                return;
+            }
+            visit(Move {
+                from,
+                to,
+                captured,
+                flags: if flying { Move::FLYING_GENERAL } else { 0 },
+            });
+        });
+    }
+
+    /// 走子後 side 方是否被將軍
+    fn leaves_in_check(&self, side: Side, from: Square, to: Square) -> bool {
+        let king = match self.kings[side as usize] {
+            Some(king) if king == from => to,
+            Some(king) => king,
+            None => return false,
+        };
+        let mut map = self.map;
+        map[to.x() as usize][to.y() as usize] = map[from.x() as usize][from.y() as usize];
+        map[from.x() as usize][from.y() as usize] = None;
+        map_king_attacked(&map, king.pos(), side, !self.rules.flying_general_capture)
+    }
+
+    /// 獲取 from 處棋子依走法可到達之所有位置（不考慮送將）
+    fn pseudo_possible_moves(&self, from: Square) -> Vec<Square> {
+        let mut targets = Vec::new();
+        self.for_each_pseudo_target(from, |to| targets.push(to));
+        targets
+    }
+
+    // 對 from 處棋子依走法可到達之每個位置調用 visit（不考慮送將）
+    fn for_each_pseudo_target(&self, from: Square, mut visit: impl FnMut(Square)) {
+        let (side, piece) = match self.piece_at(from) {
+            Some(piece) => piece,
+            None => return,
+        };
+        let own = self.occupancy[side as usize];
+        let occupied = self.occupancy[0] | self.occupancy[1];
+        let (x, y) = from.pos();
+        let mut push = |to: Square| {
+            if !own.contains(to) {
+                visit(to);
+            }
+        };
+        match piece {
+            Piece::兵 => {
+                let forward = match side {
+                    Side::Red => 1,
+                    Side::Black => -1,
+                };
+                if let Some(to) = Square::new(x, y + forward) {
+                    push(to);
+                }
+                if crossed_river(side, y) {
+                    for dx in [1, -1] {
+                        if let Some(to) = Square::new(x + dx, y) {
+                            push(to);
                         }
                     }
-                    Piece::帥 => {
-                        let left_down = match side {
-                            Side::Red => (3, 0),
-                            Side::Black => (3, 7),
-                        };
-                        let right_up = match side {
-                            Side::Red => (5, 2),
-                            Side::Black => (5, 9),
-                        };
-                        let pos = (from.0, from.1 + 1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
-                        }
-                        let pos = (from.0, from.1 - 1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
+                }
+            }
+            Piece::仕 => {
+                for (dx, dy) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                    if in_palace(side, (x + dx, y + dy)) {
+                        push(Square::new(x + dx, y + dy).unwrap());
+                    }
+                }
+            }
+            Piece::相 => {
+                for (dx, dy) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                    let (tx, ty) = (x + 2 * dx, y + 2 * dy);
+                    if crossed_river(side, ty) {
+                        continue;
+                    }
+                    if let Some(to) = Square::new(tx, ty) {
+                        // 塞象眼
+                        if !occupied.contains(Square::new(x + dx, y + dy).unwrap()) {
+                            push(to);
                         }
-                        let pos = (from.0 + 1, from.1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
+                    }
+                }
+            }
+            Piece::馬 => {
+                for (dx, dy) in HORSE_STEPS {
+                    if let Some(to) = Square::new(x + dx, y + dy) {
+                        // 蹩馬腿
+                        let leg = Square::new(x + dx / 2, y + dy / 2).unwrap();
+                        if !occupied.contains(leg) {
+                            push(to);
                         }
-                        let pos = (from.0 - 1, from.1);
-                        if position_inside(pos, left_down, right_up)
-                            && !self.has_friend_at(side, pos)
-                        {
-                            ret.push(pos);
+                    }
+                }
+            }
+            Piece::車 => {
+                for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    let mut pos = (x + dx, y + dy);
+                    while let Some(to) = Square::new(pos.0, pos.1) {
+                        push(to);
+                        if occupied.contains(to) {
+                            break;
                         }
-                        for y in 0..10 {
-                            let pos = (from.0, y);
-                            if self.has_enemy_at(side, pos) {
-                                let target = self.map[pos.0 as usize][pos.1 as usize].unwrap().1;
-                                match target {
-                                    Piece::帥 => match side {
-                                        Side::Red => {
-                                            if self.piece_count(from, pos) == 2 {
-                                                ret.push(pos);
-                                            }
-                                        }
-                                        Side::Black => {
-                                            if self.piece_count(pos, from) == 2 {
-                                                ret.push(pos);
-                                            }
-                                        }
-                                    },
-                                    _ => (),
-                                }
+                        pos = (pos.0 + dx, pos.1 + dy);
+                    }
+                }
+            }
+            Piece::炮 => {
+                for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    let mut pos = (x + dx, y + dy);
+                    let mut screened = false;
+                    while let Some(to) = Square::new(pos.0, pos.1) {
+                        if !screened {
+                            if occupied.contains(to) {
+                                screened = true;
+                            } else {
+                                push(to);
                             }
+                        } else if occupied.contains(to) {
+                            push(to);
+                            break;
                         }
+                        pos = (pos.0 + dx, pos.1 + dy);
                     }
                 }
-                if self.looped() {
-                    debug_assert!(self.undo_move_records.len() >= 4);
-                    return ret.into_iter().filter(|to| {
-                        let records = &self.undo_move_records;
-                        let len = records.len();
-                        from != records[len - 4].from_pos
-                            || to.clone() != records[len - 4].to_pos
-                    }).collect();
-                } else {
-                    return ret;
-                }
             }
-            None => {
-                return Vec::new();
+            Piece::帥 => {
+                for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    if in_palace(side, (x + dx, y + dy)) {
+                        push(Square::new(x + dx, y + dy).unwrap());
+                    }
+                }
+                // 帥帥照面時可吃對方之帥
+                let dy = match side {
+                    Side::Red => 1,
+                    Side::Black => -1,
+                };
+                let mut pos = (x, y + dy);
+                while let Some(to) = Square::new(pos.0, pos.1) {
+                    if occupied.contains(to) {
+                        if self.piece_at(to) == Some((side.other(), Piece::帥)) {
+                            push(to);
+                        }
+                        break;
+                    }
+                    pos = (pos.0, pos.1 + dy);
+                }
             }
         }
     }
@@ -525,52 +820,429 @@ impl Board {
                     }
                 }
             }
-            println!("");
+            println!();
         }
     }
 
     // 檢查並進行移動
-    pub fn do_move(&mut self, from: (i32, i32), to: (i32, i32)) -> Result<(), ()> {
-        let possible_moves = self.all_possible_moves(from);
+    pub fn do_move(&mut self, step: Move) -> Result<(), MoveError> {
+        if self.finished() {
+            return Err(MoveError::GameFinished);
+        }
+        let side = match self.piece_at(step.from) {
+            Some(piece) => piece.0,
+            None => return Err(MoveError::NoPiece(step.from)),
+        };
+        if side != self.side_to_move {
+            return Err(MoveError::NotYourTurn {
+                side_to_move: self.side_to_move,
+            });
+        }
+        let possible_moves = self.all_possible_moves(step.from);
         for possible_move in possible_moves {
-            if to == possible_move {
-                self.do_move_unchecked(from, to);
+            if step.same_squares(&possible_move) {
+                self.do_move_unchecked(possible_move);
                 return Ok(());
             }
         }
-        Err(())
+        if self.pseudo_possible_moves(step.from).contains(&step.to)
+            && self.leaves_in_check(side, step.from, step.to)
+        {
+            Err(MoveError::LeavesGeneralInCheck(step))
+        } else {
+            Err(MoveError::IllegalDestination(step))
+        }
     }
 
     // 進行移動，但不做檢查
-    pub fn do_move_unchecked(&mut self, from: (i32, i32), to: (i32, i32)) {
-        debug_assert!(position_inside_board(from));
-        debug_assert!(position_inside_board(to));
-        self.move_count += 1;
-        if self.has_piece_at(to) {
-            if self.map[to.0 as usize][to.1 as usize].unwrap().1 == Piece::帥 {
-                self.game_finished = true;
-                self.winner = Some(self.map[to.0 as usize][to.1 as usize].unwrap().0.other());
+    pub fn do_move_unchecked(&mut self, step: Move) {
+        debug_assert!(self.has_friend_at(self.side_to_move, step.from));
+        let captured = self.piece_at(step.to);
+        self.make_move(step);
+        if let Some((side, Piece::帥)) = captured {
+            self.finish(
+                GameResult::win_for(side.other()),
+                Termination::GeneralCaptured,
+            );
+        }
+        if self.outcome.is_none() {
+            self.finish_if_no_legal_move();
+        }
+        if self.outcome.is_none() {
+            if let Some(result) = self.repetition_result() {
+                self.finish(result, Termination::Repetition);
+            }
+        }
+        if let Some(limit) = self.rules.halfmove_limit {
+            if self.outcome.is_none() && self.halfmove_clock >= limit {
+                self.finish(GameResult::Draw, Termination::MoveLimit);
             }
         }
-        if self.move_count >= 2000 {
-            self.game_finished = true;
+    }
+
+    // 走子方無著可走時結束對局：將死告負，困斃依規則告負或判和
+    fn finish_if_no_legal_move(&mut self) {
+        let side = self.side_to_move;
+        if self.has_legal_move(side) {
+            return;
         }
+        if self.is_in_check(side) {
+            self.finish(GameResult::win_for(side.other()), Termination::Checkmate);
+        } else if self.rules.stalemate_loses {
+            self.finish(GameResult::win_for(side.other()), Termination::Stalemate);
+        } else {
+            self.finish(GameResult::Draw, Termination::Stalemate);
+        }
+    }
+
+    // 更新棋盤、歷史、計數與 Zobrist 鍵，不判斷終局
+    fn make_move(&mut self, step: Move) {
+        let from_piece = self.piece_at(step.from);
+        let to_piece = self.piece_at(step.to);
+        self.move_count += 1;
         self.undo_move_records.push(UndoMoveRecord {
-            from_pos: from,
-            from_piece: self.map[from.0 as usize][from.1 as usize],
-            to_pos: to,
-            to_piece: self.map[to.0 as usize][to.1 as usize],
+            step,
+            from_piece,
+            to_piece,
+            outcome: self.outcome,
+            halfmove_clock: self.halfmove_clock,
+            hash: self.hash,
         });
-        if self.map[to.0 as usize][to.1 as usize].is_some() {
+        self.toggle_move_hash(step, from_piece, to_piece);
+        if to_piece.is_some() {
+            self.halfmove_clock = 0;
             self.sum_piece -= 1;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if self.side_to_move == Side::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = self.side_to_move.other();
+        self.set_piece(step.to, from_piece);
+        self.set_piece(step.from, None);
+        debug_assert_eq!(self.hash, self.compute_hash());
+    }
+
+    // 放置或移除某處棋子，同時更新位棋盤與帥之位置
+    fn set_piece(&mut self, square: Square, piece: Option<(Side, Piece)>) {
+        if let Some((side, kind)) = self.piece_at(square) {
+            self.occupancy[side as usize].remove(square);
+            if kind == Piece::帥 && self.kings[side as usize] == Some(square) {
+                self.kings[side as usize] = None;
+            }
+        }
+        if let Some((side, kind)) = piece {
+            self.occupancy[side as usize].insert(square);
+            if kind == Piece::帥 {
+                self.kings[side as usize] = Some(square);
+            }
+        }
+        self.map[square.x() as usize][square.y() as usize] = piece;
+    }
+
+    // 依地圖重建位棋盤與帥之位置
+    fn rebuild_piece_sets(&mut self) {
+        self.occupancy = [Bitboard::EMPTY; 2];
+        self.kings = [None; 2];
+        for square in Square::all() {
+            let piece = self.piece_at(square);
+            self.set_piece(square, piece);
+        }
+    }
+
+    // 撤銷 make_move 之改動
+    fn unmake_move(&mut self) -> Option<UndoMoveRecord> {
+        let record = self.undo_move_records.pop()?;
+        self.set_piece(record.step.from, record.from_piece);
+        self.set_piece(record.step.to, record.to_piece);
+        self.outcome = record.outcome;
+        self.halfmove_clock = record.halfmove_clock;
+        self.side_to_move = self.side_to_move.other();
+        if self.side_to_move == Side::Black {
+            self.fullmove_number -= 1;
+        }
+        self.move_count -= 1;
+        if record.to_piece.is_some() {
+            self.sum_piece += 1;
+        }
+        self.toggle_move_hash(record.step, record.from_piece, record.to_piece);
+        debug_assert_eq!(self.hash, self.compute_hash());
+        Some(record)
+    }
+
+    // 當前局面在不可逆著法之後、同一方走子時出現過之歷史位置（自近而遠）
+    fn earlier_occurrences(&self, hash: u64) -> impl Iterator<Item = usize> + '_ {
+        let records = &self.undo_move_records;
+        let len = records.len();
+        let window = (self.halfmove_clock as usize).min(len);
+        (2..=window)
+            .step_by(2)
+            .map(move |back| len - back)
+            .filter(move |i| records[*i].hash == hash)
+    }
+
+    /// 當前局面重複出現之次數（含當前局面）
+    pub fn repetition_count(&self) -> usize {
+        self.earlier_occurrences(self.hash).count() + 1
+    }
+
+    // 當前局面已達重複次數時，按長將、長捉判負，其餘判和
+    fn repetition_result(&self) -> Option<GameResult> {
+        if self.repetition_count() < self.rules.repetition_count {
+            return None;
+        }
+        if self.rules.repetition == RepetitionRule::AlwaysDraw {
+            return Some(GameResult::Draw);
+        }
+        let start = self.earlier_occurrences(self.hash).next()?;
+        let plies = self.undo_move_records.len() - start;
+        // 回到循環起點，逐著重演以判斷每著是否將軍、捉子
+        let mut board = self.clone();
+        for _ in 0..plies {
+            board.unmake_move();
+        }
+        // 中國規則下將、捉交替亦計入長捉
+        let mixed = self.rules.repetition == RepetitionRule::Chinese;
+        let mut perpetual_check = [true, true];
+        let mut perpetual_chase = [true, true];
+        for i in start..self.undo_move_records.len() {
+            let step = self.undo_move_records[i].step;
+            let mover = board.side_to_move;
+            board.make_move(step);
+            let check = board.is_in_check(mover.other());
+            let chase = board.chases(step);
+            perpetual_check[mover as usize] &= check;
+            perpetual_chase[mover as usize] &= if mixed {
+                check || chase
+            } else {
+                !check && chase
+            };
+        }
+        let loser = match (perpetual_check, perpetual_chase) {
+            ([true, false], _) => Some(Side::Red),
+            ([false, true], _) => Some(Side::Black),
+            ([true, true], _) => None,
+            (_, [true, false]) => Some(Side::Red),
+            (_, [false, true]) => Some(Side::Black),
+            _ => None,
+        };
+        Some(match loser {
+            Some(loser) => GameResult::win_for(loser.other()),
+            None => GameResult::Draw,
+        })
+    }
+
+    // 走此著是否會因長將、長捉而判負
+    fn loses_by_repetition(&self, step: Move) -> bool {
+        if step.is_capture() || !self.has_friend_at(self.side_to_move, step.from) {
+            return false;
+        }
+        let mover = self.side_to_move;
+        let mut hash = self.hash ^ zobrist::side_key();
+        if let Some(moving) = self.piece_at(step.from) {
+            hash ^= zobrist::piece_key(moving, step.from.index());
+            hash ^= zobrist::piece_key(moving, step.to.index());
+        }
+        // 走後局面在走子前之歷史中與其同一方走子者位於 len - 1, len - 3, ...
+        let records = &self.undo_move_records;
+        let len = records.len();
+        let window = (self.halfmove_clock as usize + 1).min(len);
+        let occurrences = (1..=window)
+            .step_by(2)
+            .filter(|back| records[len - back].hash == hash)
+            .count();
+        if occurrences + 1 < self.rules.repetition_count
+            || self.rules.repetition == RepetitionRule::AlwaysDraw
+        {
+            return false;
+        }
+        let mut board = self.clone();
+        board.make_move(step);
+        board.repetition_result() == Some(GameResult::win_for(mover.other()))
+    }
+
+    // 剛走之子是否捉對方無根子（或以馬、炮捉車），帥與兵之捉不計
+    fn chases(&mut self, step: Move) -> bool {
+        let attacker = match self.piece_at(step.to) {
+            Some(piece) => piece,
+            None => return false,
+        };
+        if attacker.1 == Piece::帥 || attacker.1 == Piece::兵 {
+            return false;
+        }
+        for capture in self.all_possible_moves(step.to) {
+            let target = match capture.captured {
+                Some(target) => target,
+                None => continue,
+            };
+            // 捉未過河之兵不算捉
+            let crossed = crossed_river(attacker.0.other(), capture.to.y());
+            if target == Piece::帥 || target == Piece::兵 && !crossed {
+                continue;
+            }
+            if target == Piece::車 && (attacker.1 == Piece::馬 || attacker.1 == Piece::炮) {
+                return true;
+            }
+            // 假想吃子後，對方能否於該處反吃
+            let target_piece = self.piece_at(capture.to);
+            self.set_piece(capture.to, Some(attacker));
+            self.set_piece(capture.from, None);
+            let protected = self.occupancy[attacker.0.other() as usize]
+                .squares()
+                .any(|from| {
+                    self.all_possible_moves(from)
+                        .iter()
+                        .any(|step| step.to == capture.to)
+                });
+            self.set_piece(capture.from, Some(attacker));
+            self.set_piece(capture.to, target_piece);
+            if !protected {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// 統計自當前局面走 depth 步之合法著法序列數目，用以驗證走法生成
+    ///
+    /// 僅排除送將之著，不計重複局面、自然限著等終局規則。
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut nodes = 0;
+        for step in self.perft_moves() {
+            if depth == 1 {
+                nodes += 1;
+                continue;
+            }
+            self.make_move(step);
+            nodes += self.perft(depth - 1);
+            self.unmake_move();
+        }
+        nodes
+    }
+
+    /// 按首著分列 perft 結果，便於與其他程序逐著對照
+    pub fn divide(&mut self, depth: u32) -> Vec<(Move, u64)> {
+        let mut result = Vec::new();
+        for step in self.perft_moves() {
+            self.make_move(step);
+            let nodes = if depth == 0 { 0 } else { self.perft(depth - 1) };
+            self.unmake_move();
+            result.push((step, nodes));
+        }
+        result
+    }
+
+    // 走子方之所有著法（僅排除送將）
+    fn perft_moves(&self) -> MoveList {
+        let mut moves = MoveList::new();
+        for from in self.occupancy[self.side_to_move as usize].squares() {
+            self.generate_moves(from, false, |step| moves.push(step));
+        }
+        moves
+    }
+
+    /// 獲取局面之 Zobrist 鍵（含走子方）
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// 自頭計算局面之 Zobrist 鍵
+    fn compute_hash(&self) -> u64 {
+        let mut hash = 0;
+        for square in Square::all() {
+            if let Some(piece) = self.piece_at(square) {
+                hash ^= zobrist::piece_key(piece, square.index());
+            }
+        }
+        if self.side_to_move == Side::Black {
+            hash ^= zobrist::side_key();
+        }
+        hash
+    }
+
+    // 走子與悔棋對 Zobrist 鍵之改動相同：移出起點、移入終點、移除被吃子、換走子方
+    fn toggle_move_hash(
+        &mut self,
+        step: Move,
+        moving: Option<(Side, Piece)>,
+        captured: Option<(Side, Piece)>,
+    ) {
+        if let Some(moving) = moving {
+            self.hash ^= zobrist::piece_key(moving, step.from.index());
+            self.hash ^= zobrist::piece_key(moving, step.to.index());
+        }
+        if let Some(captured) = captured {
+            self.hash ^= zobrist::piece_key(captured, step.to.index());
         }
-        self.map[to.0 as usize][to.1 as usize] = self.map[from.0 as usize][from.1 as usize];
-        self.map[from.0 as usize][from.1 as usize] = None;
+        self.hash ^= zobrist::side_key();
+    }
+
+    /// 以給定結果結束對局（已結束者保留原結果）
+    fn finish(&mut self, result: GameResult, termination: Termination) {
+        if self.outcome.is_none() {
+            self.outcome = Some((result, termination));
+        }
+    }
+
+    /// side 方認輸
+    pub fn resign(&mut self, side: Side) {
+        self.finish(GameResult::win_for(side.other()), Termination::Resignation);
+    }
+
+    /// 雙方議和
+    pub fn agree_draw(&mut self) {
+        self.finish(GameResult::Draw, Termination::Agreement);
+    }
+
+    /// side 方超時判負
+    pub fn time_out(&mut self, side: Side) {
+        self.finish(GameResult::win_for(side.other()), Termination::Timeout);
+    }
+
+    /// 獲取自上次吃子以來之步數
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// 獲取對局規則
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// side 方是否尚有合法著法
+    pub fn has_legal_move(&self, side: Side) -> bool {
+        self.occupancy[side as usize]
+            .squares()
+            .any(|from| !self.all_possible_moves(from).is_empty())
+    }
+
+    /// side 方是否正被將軍
+    pub fn is_in_check(&self, side: Side) -> bool {
+        map_in_check(&self.map, side, !self.rules.flying_general_capture)
     }
 
     // 遊戲是否結束
     pub fn finished(&self) -> bool {
-        self.game_finished
+        self.outcome.is_some()
+    }
+
+    /// 獲取對局結果，未結束則爲 None
+    pub fn result(&self) -> Option<GameResult> {
+        self.outcome.map(|outcome| outcome.0)
+    }
+
+    /// 獲取終局原因，未結束則爲 None
+    pub fn termination(&self) -> Option<Termination> {
+        self.outcome.map(|outcome| outcome.1)
+    }
+
+    /// 獲取當前走子方
+    pub fn side_to_move(&self) -> Side {
+        self.side_to_move
     }
 
     /// 獲取地圖
@@ -580,80 +1252,59 @@ impl Board {
 
     /// 獲取贏家
     pub fn get_winner(&self) -> Option<Side> {
-        self.winner
+        self.result().and_then(|result| result.winner())
+    }
+
+    /// 獲取某處棋子
+    pub fn piece_at(&self, square: Square) -> Option<(Side, Piece)> {
+        self.map[square.x() as usize][square.y() as usize]
     }
 
     /// 查詢某處是否有己方棋子
-    pub fn has_friend_at(&self, side: Side, pos: (i32, i32)) -> bool {
-        let piece = self.map[pos.0 as usize][pos.1 as usize];
-        match piece {
-            Some(piece) => piece.0 == side,
-            None => false,
-        }
+    pub fn has_friend_at(&self, side: Side, square: Square) -> bool {
+        self.occupancy[side as usize].contains(square)
     }
 
     /// 查詢某處是否有對方棋子
-    pub fn has_enemy_at(&self, side: Side, pos: (i32, i32)) -> bool {
-        self.has_friend_at(side.other(), pos)
+    pub fn has_enemy_at(&self, side: Side, square: Square) -> bool {
+        self.occupancy[side.other() as usize].contains(square)
     }
 
     /// 查詢某處是否有棋子
-    pub fn has_piece_at(&self, pos: (i32, i32)) -> bool {
-        match self.map[pos.0 as usize][pos.1 as usize] {
-            Some(_) => true,
-            None => false,
-        }
+    pub fn has_piece_at(&self, square: Square) -> bool {
+        self.piece_at(square).is_some()
     }
 
-    /// 最後四步是否循環
-    pub fn looped(&self) -> bool {
-        let records = &self.undo_move_records;
-        if records.len() >= 4 {
-            let len = records.len();
-            records[len - 4].to_piece.is_none()
-                && records[len - 3].to_piece.is_none()
-                && records[len - 2].to_piece.is_none()
-                && records[len - 1].to_piece.is_none()
-                && records[len - 4].from_pos == records[len - 2].to_pos
-                && records[len - 4].to_pos == records[len - 2].from_pos
-                && records[len - 3].from_pos == records[len - 1].to_pos
-                && records[len - 3].to_pos == records[len - 1].from_pos
-        } else {
-            false
+    // 撤銷移動；對局以認輸、議和或超時結束者，僅撤銷該結束而保留最後一著
+    pub fn undo_move(&mut self) -> Result<(), MoveError> {
+        if matches!(
+            self.termination(),
+            Some(Termination::Resignation | Termination::Agreement | Termination::Timeout)
+        ) {
+            self.outcome = None;
+            return Ok(());
         }
-    }
-
-    // 撤銷移動
-    pub fn undo_move(&mut self) -> Result<(), ()> {
-        if self.undo_move_records.len() == 0 {
-            Err(())
-        } else {
-            let record = self.undo_move_records.pop().unwrap();
-            self.map[record.from_pos.0 as usize][record.from_pos.1 as usize] = record.from_piece;
-            self.map[record.to_pos.0 as usize][record.to_pos.1 as usize] = record.to_piece;
-            self.game_finished = false;
-            self.winner = None;
-            self.move_count -= 1;
-            if record.to_piece.is_some() {
-                self.sum_piece += 1;
-            }
-            Ok(())
+        match self.unmake_move() {
+            Some(_) => Ok(()),
+            None => Err(MoveError::NothingToUndo),
         }
     }
 
     /// 獲取某範圍內棋子數目
-    pub fn piece_count(&self, left_down: (i32, i32), right_up: (i32, i32)) -> i32 {
+    pub fn piece_count(&self, left_down: Square, right_up: Square) -> i32 {
+        self.count_between(left_down.pos(), right_up.pos())
+    }
+
+    // 以座標獲取某範圍內棋子數目
+    fn count_between(&self, left_down: (i32, i32), right_up: (i32, i32)) -> i32 {
         if left_down == (0, 0) && right_up == (8, 9) {
             return self.piece_count_of_board();
         }
         let mut cnt = 0;
         for x in left_down.0..(right_up.0 + 1) {
             for y in left_down.1..(right_up.1 + 1) {
-                match self.map[x as usize][y as usize] {
-                    Some(_) => {
-                        cnt += 1;
-                    }
-                    None => (),
+                if self.map[x as usize][y as usize].is_some() {
+                    cnt += 1;
                 }
             }
         }
@@ -665,3 +1316,323 @@ impl Board {
         self.sum_piece
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn perft_of(fen: &str, depth: u32) -> u64 {
+        Board::from_fen(fen).unwrap().perft(depth)
+    }
+
+    #[test]
+    fn perft_start_position() {
+        let mut board = Board::new();
+        assert_eq!(board.perft(1), 44);
+        assert_eq!(board.perft(2), 1920);
+        assert_eq!(board.perft(3), 79666);
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let mut board = Board::new();
+        let divided = board.divide(2);
+        assert_eq!(divided.len(), 44);
+        assert_eq!(divided.iter().map(|(_, nodes)| nodes).sum::<u64>(), 1920);
+    }
+
+    #[test]
+    fn perft_known_positions() {
+        // 各局面 1 至 3 層之節點數
+        let positions = [
+            (
+                "r1ba1a3/4kn3/2n1b4/pNp1p1p1p/4c4/6P2/P1P2R2P/1CcC5/9/2BAKAB2 w - - 0 1",
+                [38, 1128, 43929],
+            ),
+            (
+                "1cbak4/9/n2a5/2p1p3p/5cp2/2n2N3/6PCP/3AB4/2C6/3A1K1N1 w - - 0 1",
+                [7, 281, 8620],
+            ),
+            (
+                "5a3/3k5/3aR4/9/5r3/5n3/9/3A1A3/5K3/2BC2B2 w - - 0 1",
+                [25, 424, 9850],
+            ),
+            (
+                "CRN1k1b2/3ca4/4ba3/9/2nr5/9/9/4B4/4A4/4KA3 w - - 0 1",
+                [28, 516, 14808],
+            ),
+            (
+                "R1N1k1b2/9/3aba3/9/2nr5/2B6/9/4B4/4A4/4KA3 w - - 0 1",
+                [21, 364, 7626],
+            ),
+            (
+                "C1nNk4/9/9/9/9/9/n1pp5/B3C4/9/3A1K3 w - - 0 1",
+                [28, 222, 6241],
+            ),
+            (
+                "4ka3/4a4/9/9/4N4/p8/9/4C3c/7n1/2BK5 w - - 0 1",
+                [23, 345, 8124],
+            ),
+            (
+                "2b1ka3/9/b3N4/4n4/9/9/9/4C4/2p6/2BK5 w - - 0 1",
+                [21, 195, 3883],
+            ),
+            (
+                "1C2ka3/9/C1Nab1n2/p3p3p/6p2/9/P3P3P/3AB4/3p2c2/c1BAK4 w - - 0 1",
+                [30, 830, 22787],
+            ),
+            (
+                "CnN1k1b2/c3a4/4ba3/9/2nr5/9/9/4C4/4A4/4KA3 w - - 0 1",
+                [19, 583, 11714],
+            ),
+            (
+                "1rbaka2R/5r3/6n2/2p1p1p2/4P1bP1/PpC3Bc1/1nPR2P2/2N2AN2/1c2K1p2/2BAC4 w - - 0 1",
+                [49, 2265, 100326],
+            ),
+        ];
+        for (fen, expected) in positions {
+            for (depth, nodes) in expected.into_iter().enumerate() {
+                assert_eq!(
+                    perft_of(fen, depth as u32 + 1),
+                    nodes,
+                    "{} 第 {} 層",
+                    fen,
+                    depth + 1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        let board = Board::from_fen(START_FEN).unwrap();
+        assert_eq!(board.to_fen(), START_FEN);
+        assert_eq!(Board::new().to_fen(), START_FEN);
+        assert_eq!(board.hash(), Board::new().hash());
+        assert_eq!(board.get_map(), Board::new().get_map());
+        for fen in [
+            "r1ba1a3/4kn3/2n1b4/pNp1p1p1p/4c4/6P2/P1P2R2P/1CcC5/9/2BAKAB2 w - - 0 1",
+            "4ka3/4a4/9/9/4N4/p8/9/4C3c/7n1/2BK5 b - - 12 40",
+        ] {
+            assert_eq!(Board::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn fen_rejects_invalid_positions() {
+        // 黑方未走子而紅帥已被黑車將軍
+        assert_eq!(
+            Board::from_fen("4k4/9/9/9/9/9/9/9/9/r3K4 b - - 0 1").unwrap_err(),
+            FenError::NotToMoveInCheck(Side::Red)
+        );
+        // 對面將
+        assert_eq!(
+            Board::from_fen("4k4/9/9/9/9/9/9/9/9/4K4 w - - 0 1").unwrap_err(),
+            FenError::NotToMoveInCheck(Side::Black)
+        );
+        assert_eq!(
+            Board::from_fen("4k4/9/9/9/9/9/9/9/9/3K05 w - - 0 1").unwrap_err(),
+            FenError::InvalidPiece('0')
+        );
+    }
+
+    #[test]
+    fn undo_reverts_only_declared_ending() {
+        let endings: [fn(&mut Board); 3] = [
+            |board| board.resign(Side::Black),
+            |board| board.agree_draw(),
+            |board| board.time_out(Side::Black),
+        ];
+        for end in endings {
+            let mut board = Board::new();
+            board.do_move(Move::from_iccs("h2e2").unwrap()).unwrap();
+            let fen = board.to_fen();
+            end(&mut board);
+            assert!(board.finished());
+            board.undo_move().unwrap();
+            assert!(!board.finished());
+            assert_eq!(board.to_fen(), fen);
+            board.undo_move().unwrap();
+            assert_eq!(board.to_fen(), START_FEN);
+        }
+        let mut board = Board::new();
+        board.resign(Side::Red);
+        board.undo_move().unwrap();
+        assert!(!board.finished());
+        assert_eq!(board.undo_move(), Err(MoveError::NothingToUndo));
+    }
+
+    #[test]
+    fn checkmate_ends_game() {
+        let mut board = Board::from_fen("3k5/R8/9/9/9/9/9/9/9/1R2K4 w - - 0 1").unwrap();
+        board.do_move(Move::from_iccs("b0b9").unwrap()).unwrap();
+        assert!(board.is_in_check(Side::Black));
+        assert_eq!(board.result(), Some(GameResult::RedWins));
+        assert_eq!(board.termination(), Some(Termination::Checkmate));
+    }
+
+    #[test]
+    fn stalemate_follows_rules() {
+        // 黑將不被將軍而無著可走
+        let fen = "3k5/9/9/9/9/9/9/9/R8/4K4 w - - 0 1";
+        let stalemate = Move::from_iccs("a1a8").unwrap();
+        let mut board = Board::from_fen(fen).unwrap();
+        board.do_move(stalemate).unwrap();
+        assert!(!board.is_in_check(Side::Black));
+        assert_eq!(board.result(), Some(GameResult::RedWins));
+        assert_eq!(board.termination(), Some(Termination::Stalemate));
+        let rules = Rules {
+            stalemate_loses: false,
+            ..Rules::asian()
+        };
+        let mut board = Board::from_fen_with_rules(fen, rules).unwrap();
+        board.do_move(stalemate).unwrap();
+        assert_eq!(board.result(), Some(GameResult::Draw));
+        assert_eq!(board.termination(), Some(Termination::Stalemate));
+    }
+
+    #[test]
+    fn is_in_check() {
+        let board = Board::new();
+        assert!(!board.is_in_check(Side::Red));
+        assert!(!board.is_in_check(Side::Black));
+        for (fen, in_check) in [
+            // 馬，蹩腳則否
+            ("4k4/9/3N5/9/9/9/9/9/9/3K5 b - - 0 1", true),
+            ("4k4/3p5/3N5/9/9/9/9/9/9/3K5 b - - 0 1", false),
+            // 炮須隔一子
+            ("4k4/9/4p4/9/4C4/9/9/9/9/3K5 b - - 0 1", true),
+            ("4k4/9/9/9/4C4/9/9/9/9/3K5 b - - 0 1", false),
+            // 兵前進或過河後橫走
+            ("4k4/4P4/9/9/9/9/9/9/9/3K5 b - - 0 1", true),
+            ("3Pk4/9/9/9/9/9/9/9/9/3K5 b - - 0 1", true),
+            // 車
+            ("4k4/9/9/9/9/9/9/9/9/3K4R b - - 0 1", false),
+            ("4k3R/9/9/9/9/9/9/9/9/3K5 b - - 0 1", true),
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(board.is_in_check(Side::Black), in_check, "{}", fen);
+            assert!(!board.is_in_check(Side::Red), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn move_limit_draw_is_undone() {
+        let fen = "3k5/9/9/9/9/9/9/9/9/R3K4 w - - 119 80";
+        let mut board = Board::from_fen(fen).unwrap();
+        assert!(!board.finished());
+        board.do_move(Move::from_iccs("a0a1").unwrap()).unwrap();
+        assert_eq!(board.result(), Some(GameResult::Draw));
+        assert_eq!(board.termination(), Some(Termination::MoveLimit));
+        board.undo_move().unwrap();
+        assert_eq!(board.halfmove_clock(), 119);
+        assert!(!board.finished());
+        assert_eq!(board.to_fen(), fen);
+    }
+
+    // 依次走出 ICCS 著法，並檢查對局是否恰於最後一著結束
+    fn play(board: &mut Board, moves: &[&str]) {
+        for (i, step) in moves.iter().enumerate() {
+            assert!(!board.finished(), "第 {} 著前對局已結束", i + 1);
+            board.do_move(Move::from_iccs(step).unwrap()).unwrap();
+        }
+        assert_eq!(board.termination(), Some(Termination::Repetition));
+    }
+
+    #[test]
+    fn idle_repetition_is_draw() {
+        let mut board = Board::from_fen("3k5/9/9/9/9/9/9/9/9/5K3 w - - 0 1").unwrap();
+        play(
+            &mut board,
+            &[
+                "f0f1", "d9d8", "f1f0", "d8d9", "f0f1", "d9d8", "f1f0", "d8d9",
+            ],
+        );
+        assert_eq!(board.result(), Some(GameResult::Draw));
+    }
+
+    #[test]
+    fn perpetual_check_loses() {
+        let mut board = Board::from_fen("3k5/9/9/9/9/9/9/9/9/R3K4 w - - 0 1").unwrap();
+        play(
+            &mut board,
+            &[
+                "a0a9", "d9d8", "a9a8", "d8d9", "a8a9", "d9d8", "a9a8", "d8d9", "a8a9",
+            ],
+        );
+        assert_eq!(board.result(), Some(GameResult::BlackWins));
+    }
+
+    #[test]
+    fn perpetual_chase_of_unprotected_piece_loses() {
+        // 紅車反覆捉無根之黑炮
+        let mut board = Board::from_fen("3k5/9/9/9/c8/9/R8/9/9/5K3 b - - 0 1").unwrap();
+        play(
+            &mut board,
+            &[
+                "a5b5", "a3b3", "b5a5", "b3a3", "a5b5", "a3b3", "b5a5", "b3a3",
+            ],
+        );
+        assert_eq!(board.result(), Some(GameResult::BlackWins));
+    }
+
+    #[test]
+    fn alternating_check_and_chase() {
+        // 紅車一將一捉：亞洲規則判和，中國規則判負
+        let fen = "3k5/9/9/9/R1c6/9/9/9/9/5K3 w - - 0 1";
+        let moves = [
+            "a5a9", "d9d8", "a9a5", "d8d9", "a5a9", "d9d8", "a9a5", "d8d9",
+        ];
+        let mut board = Board::from_fen_with_rules(fen, Rules::asian()).unwrap();
+        play(&mut board, &moves);
+        assert_eq!(board.result(), Some(GameResult::Draw));
+        let mut board = Board::from_fen_with_rules(fen, Rules::chinese()).unwrap();
+        play(&mut board, &moves);
+        assert_eq!(board.result(), Some(GameResult::BlackWins));
+    }
+
+    #[test]
+    fn casual_rules_always_draw() {
+        let mut board =
+            Board::from_fen_with_rules("3k5/9/9/9/9/9/9/9/9/R3K4 w - - 0 1", Rules::casual())
+                .unwrap();
+        play(
+            &mut board,
+            &[
+                "a0a9", "d9d8", "a9a8", "d8d9", "a8a9", "d9d8", "a9a8", "d8d9", "a8a9",
+            ],
+        );
+        assert_eq!(board.result(), Some(GameResult::Draw));
+    }
+
+    #[test]
+    fn flying_general_capture() {
+        // 紅兵離開中路即成帥帥照面
+        let fen = "4k4/9/9/9/4P4/9/9/9/9/4K4 w - - 0 1";
+        let expose = Move::from_iccs("e5d5").unwrap();
+        let mut board = Board::from_fen_with_rules(fen, Rules::asian()).unwrap();
+        assert!(matches!(
+            board.do_move(expose),
+            Err(MoveError::LeavesGeneralInCheck(_))
+        ));
+        let mut board = Board::from_fen_with_rules(fen, Rules::casual()).unwrap();
+        board.do_move(expose).unwrap();
+        assert!(!board.is_in_check(Side::Red));
+        board.do_move(Move::from_iccs("e9e0").unwrap()).unwrap();
+        assert_eq!(board.result(), Some(GameResult::BlackWins));
+        assert_eq!(board.termination(), Some(Termination::GeneralCaptured));
+    }
+
+    #[test]
+    #[ignore]
+    fn perft_deep() {
+        assert_eq!(perft_of(START_FEN, 4), 3290240);
+        assert_eq!(
+            perft_of(
+                "1rbaka2R/5r3/6n2/2p1p1p2/4P1bP1/PpC3Bc1/1nPR2P2/2N2AN2/1c2K1p2/2BAC4 w - - 0 1",
+                4
+            ),
+            4485547
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::time::{Duration, Instant};
+
+/// 未給出距下一時限之步數時，假定餘下之步數
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// 爲通訊及走子等延遲預留之時間
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+/// 某方之對局時鐘
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    /// 剩餘時間
+    pub remaining: Duration,
+    /// 每步加秒
+    pub increment: Duration,
+    /// 距下一時限之步數，None 表示以剩餘時間走完全局
+    pub moves_to_go: Option<u32>,
+}
+
+/// 搜索限制：各項同時生效，任一項達到即停止，全爲 None 則不限
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// 最大搜索深度
+    pub depth: Option<u32>,
+    /// 最大節點數（各層合計）
+    pub nodes: Option<u64>,
+    /// 每步用時
+    pub move_time: Option<Duration>,
+    /// 對局時鐘，由時間管理分配每步用時
+    pub clock: Option<Clock>,
+}
+
+impl SearchLimits {
+    /// 固定深度
+    pub fn depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    /// 固定節點數
+    pub fn nodes(nodes: u64) -> Self {
+        Self {
+            nodes: Some(nodes),
+            ..Default::default()
+        }
+    }
+
+    /// 固定每步用時
+    pub fn move_time(move_time: Duration) -> Self {
+        Self {
+            move_time: Some(move_time),
+            ..Default::default()
+        }
+    }
+
+    /// 按對局時鐘用時
+    pub fn clock(clock: Clock) -> Self {
+        Self {
+            clock: Some(clock),
+            ..Default::default()
+        }
+    }
+}
+
+/// 時間管理：自搜索開始計時，給出目標用時與最長用時
+#[derive(Debug, Clone, Copy)]
+pub struct TimeManager {
+    start: Instant,
+    /// 目標用時，用去一半後不再開始更深一層
+    optimum: Option<Duration>,
+    /// 最長用時，到達即中止搜索
+    maximum: Option<Duration>,
+}
+
+impl TimeManager {
+    /// 按搜索限制分配本步用時，並開始計時
+    pub fn new(limits: &SearchLimits) -> TimeManager {
+        let mut optimum = limits.move_time;
+        let mut maximum = limits.move_time;
+        if let Some(clock) = limits.clock {
+            let available = clock.remaining.saturating_sub(MOVE_OVERHEAD);
+            let moves_to_go = clock.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+            let target = (available / moves_to_go + clock.increment * 3 / 4).min(available);
+            let limit = (target * 3).min(available);
+            optimum = Some(optimum.map_or(target, |optimum| optimum.min(target)));
+            maximum = Some(maximum.map_or(limit, |maximum| maximum.min(limit)));
+        }
+        TimeManager {
+            start: Instant::now(),
+            optimum,
+            maximum,
+        }
+    }
+
+    /// 已用時間
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// 須中止搜索之時刻，不限時則爲 None
+    pub fn deadline(&self) -> Option<Instant> {
+        self.maximum.map(|maximum| self.start + maximum)
+    }
+
+    /// 是否還應開始更深一層：下一層之用時通常數倍於此前各層之和，
+    /// 故已用去目標用時之半即停止加深
+    pub fn should_deepen(&self) -> bool {
+        self.optimum
+            .is_none_or(|optimum| self.elapsed() < optimum / 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(remaining_ms: u64, increment_ms: u64, moves_to_go: Option<u32>) -> SearchLimits {
+        SearchLimits::clock(Clock {
+            remaining: Duration::from_millis(remaining_ms),
+            increment: Duration::from_millis(increment_ms),
+            moves_to_go,
+        })
+    }
+
+    // 目標用時與最長用時（毫秒）
+    fn budget(limits: &SearchLimits) -> (Option<u128>, Option<u128>) {
+        let time_manager = TimeManager::new(limits);
+        (
+            time_manager.optimum.map(|optimum| optimum.as_millis()),
+            time_manager.maximum.map(|maximum| maximum.as_millis()),
+        )
+    }
+
+    #[test]
+    fn unlimited() {
+        let time_manager = TimeManager::new(&SearchLimits::depth(5));
+        assert!(time_manager.should_deepen());
+        assert_eq!(time_manager.deadline(), None);
+    }
+
+    #[test]
+    fn fixed_move_time() {
+        let limits = SearchLimits::move_time(Duration::from_millis(500));
+        assert_eq!(budget(&limits), (Some(500), Some(500)));
+        let time_manager = TimeManager::new(&limits);
+        assert!(time_manager.should_deepen());
+        assert_eq!(
+            time_manager.deadline(),
+            Some(time_manager.start + Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn clock_budget() {
+        // 扣除預留之 50 毫秒後按 30 步平分，最長用時爲目標之三倍
+        assert_eq!(budget(&clock(30_050, 0, None)), (Some(1000), Some(3000)));
+        // 加秒計入四分之三
+        assert_eq!(budget(&clock(30_050, 2000, None)), (Some(2500), Some(7500)));
+        assert_eq!(
+            budget(&clock(10_050, 0, Some(10))),
+            (Some(1000), Some(3000))
+        );
+        // 最後一步可用盡剩餘時間，但不超過
+        assert_eq!(
+            budget(&clock(10_050, 0, Some(1))),
+            (Some(10000), Some(10000))
+        );
+        assert_eq!(
+            budget(&clock(10_050, 0, Some(0))),
+            (Some(10000), Some(10000))
+        );
+        assert_eq!(budget(&clock(1_050, 5000, None)), (Some(1000), Some(1000)));
+    }
+
+    #[test]
+    fn move_time_with_clock() {
+        let limits = SearchLimits {
+            move_time: Some(Duration::from_millis(500)),
+            ..clock(30_050, 0, None)
+        };
+        assert_eq!(budget(&limits), (Some(500), Some(500)));
+        let limits = SearchLimits {
+            move_time: Some(Duration::from_millis(2000)),
+            ..clock(30_050, 0, None)
+        };
+        assert_eq!(budget(&limits), (Some(1000), Some(2000)));
+    }
+
+    #[test]
+    fn clock_below_overhead() {
+        let limits = clock(30, 0, None);
+        assert_eq!(budget(&limits), (Some(0), Some(0)));
+        let time_manager = TimeManager::new(&limits);
+        assert!(!time_manager.should_deepen());
+        assert_eq!(time_manager.deadline(), Some(time_manager.start));
+    }
+}
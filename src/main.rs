@@ -1,37 +1,163 @@
 pub mod ai;
+pub mod bench;
+pub mod bitboard;
 pub mod controller;
 pub mod game;
+pub mod limits;
+pub mod moves;
+pub mod notation;
+pub mod pst;
+pub mod rules;
+pub mod tt;
+pub mod tune;
+pub mod weights;
+pub mod zobrist;
 
 use ai::*;
+use bench::*;
 use controller::*;
 use game::*;
+use limits::*;
+use moves::*;
+use pst::*;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+use tt::*;
+use tune::*;
+
+// 命令行中 name 選項之值
+fn option_value<T: FromStr>(args: &[String], name: &str) -> Option<T> {
+    let index = args.iter().position(|arg| arg == name)?;
+    args.get(index + 1)?.parse().ok()
+}
+
+// 命令行中 name 選項之秒數
+fn option_seconds(args: &[String], name: &str) -> Option<Duration> {
+    Duration::try_from_secs_f64(option_value(args, name)?).ok()
+}
 
 fn main() {
+    // cargo run --release -- bench [深度]
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("bench") {
+        let depth = args
+            .get(2)
+            .and_then(|depth| depth.parse().ok())
+            .unwrap_or(4);
+        run_benchmark(depth);
+        return;
+    }
+    // cargo run --release -- --weights 權重文件（.toml 或 .json）
+    let evaluator = match args.iter().position(|arg| arg == "--weights") {
+        Some(index) => {
+            let path = match args.get(index + 1) {
+                Some(path) => path,
+                None => {
+                    println!("--weights 後須給出權重文件");
+                    return;
+                }
+            };
+            match PstEvaluator::from_file(Path::new(path)) {
+                Ok(evaluator) => evaluator,
+                Err(error) => {
+                    println!("{}", error);
+                    return;
+                }
+            }
+        }
+        None => PstEvaluator::new(),
+    };
+    // cargo run --release -- tune 數據集 輸出權重文件 [輪數] [--weights 初始權重文件]
+    if args.get(1).map(String::as_str) == Some("tune") {
+        let (dataset, output) = match (args.get(2), args.get(3)) {
+            (Some(dataset), Some(output)) => (dataset, output),
+            _ => {
+                println!("用法：tune 數據集 輸出權重文件 [輪數] [--weights 初始權重文件]");
+                return;
+            }
+        };
+        let rounds = args
+            .get(4)
+            .and_then(|rounds| rounds.parse().ok())
+            .unwrap_or(100);
+        let weights = evaluator.weights().clone();
+        if let Err(error) = run_tuning(Path::new(dataset), Path::new(output), weights, rounds) {
+            println!("{}", error);
+        }
+        return;
+    }
+    // cargo run --release -- --hash 置換表大小（MB）
+    let tt_size = option_value(&args, "--hash").unwrap_or(DEFAULT_TT_SIZE_MB);
+    // cargo run --release -- [--movetime 毫秒] [--depth 深度] [--clock 秒 [--inc 秒]]
+    let move_time = option_value(&args, "--movetime").map(Duration::from_millis);
+    let depth = option_value(&args, "--depth");
+    let clock = option_seconds(&args, "--clock");
+    let increment = option_seconds(&args, "--inc").unwrap_or(Duration::ZERO);
+    let limits = if move_time.is_some() || depth.is_some() || clock.is_some() {
+        SearchLimits {
+            depth,
+            move_time,
+            ..Default::default()
+        }
+    } else {
+        SearchLimits::nodes(10000000)
+    };
+    let mut remaining = [clock; 2];
     let mut board = Board::new();
-    let red_controller = AIController::new(ShortSightedEvaluator::new(), 10000000);
-    let black_controller = AIController::new(ShortSightedEvaluator::new(), 10000000);
-    board.do_move_unchecked((7, 2), (4, 2));
+    let red_controller = AIController::new(evaluator.clone(), 10000000).with_tt_size(tt_size);
+    let black_controller = AIController::new(evaluator.clone(), 10000000).with_tt_size(tt_size);
+    board.do_move(Move::from_iccs("h2e2").unwrap()).unwrap();
     board.display();
     while !board.finished() {
-        let black_move = black_controller.decide(Side::Black, &board).unwrap();
-        board.do_move(black_move.0, black_move.1).unwrap();
-        board.display();
+        let side = board.side_to_move();
+        let controller = match side {
+            Side::Red => &red_controller,
+            Side::Black => &black_controller,
+        };
+        let limits = SearchLimits {
+            clock: remaining[side as usize].map(|remaining| Clock {
+                remaining,
+                increment,
+                moves_to_go: None,
+            }),
+            ..limits
+        };
+        let decision = match controller.think_with_limits(&board, &limits) {
+            Some(decision) => decision,
+            None => {
+                println!("{}", MoveError::GameFinished);
+                break;
+            }
+        };
+        let pv: Vec<String> = decision.pv.iter().map(Move::to_iccs).collect();
         println!(
-            "分數 {}",
-            ShortSightedEvaluator::new().evaluate(&board, Side::Red)
+            "深度 {}，分數 {}，{} 節點，{:.3} 秒，主要變例 {}",
+            decision.depth,
+            decision.score,
+            decision.nodes,
+            decision.elapsed.as_secs_f64(),
+            pv.join(" ")
         );
-        if board.finished() {
-            break;
+        if let Some(remaining) = &mut remaining[side as usize] {
+            if decision.elapsed > *remaining {
+                board.time_out(side);
+                break;
+            }
+            *remaining = *remaining - decision.elapsed + increment;
         }
-        let red_move = red_controller.decide(Side::Red, &board).unwrap();
-        board.do_move(red_move.0, red_move.1).unwrap();
-        board.display();
-        println!(
-            "分數 {}",
-            ShortSightedEvaluator::new().evaluate(&board, Side::Red)
-        );
-        if board.finished() {
-            break;
+        let step = decision.step.unwrap();
+        match board.do_move(step) {
+            Ok(()) => println!("著法 {}", step.to_iccs()),
+            Err(error) => {
+                println!("{}", error);
+                break;
+            }
         }
+        board.display();
+        println!("分數 {}", evaluator.evaluate(&board, Side::Red));
+    }
+    if let (Some(result), Some(termination)) = (board.result(), board.termination()) {
+        println!("結果 {:?}（{:?}）", result, termination);
     }
 }
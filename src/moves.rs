@@ -0,0 +1,202 @@
+use crate::game::*;
+use std::fmt;
+
+/// 棋盤上之位置（0..90，自紅方底線起逐行編號）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+/// 不在棋盤內之座標
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSquare(pub i32, pub i32);
+
+impl Square {
+    /// 自座標構建，x 爲列（0..9），y 爲行（0..10）
+    pub fn new(x: i32, y: i32) -> Option<Square> {
+        if (0..9).contains(&x) && (0..10).contains(&y) {
+            Some(Square((y * 9 + x) as u8))
+        } else {
+            None
+        }
+    }
+
+    /// 自編號構建
+    pub fn from_index(index: usize) -> Option<Square> {
+        if index < 90 {
+            Some(Square(index as u8))
+        } else {
+            None
+        }
+    }
+
+    /// 獲取編號
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// 獲取列
+    pub fn x(self) -> i32 {
+        self.0 as i32 % 9
+    }
+
+    /// 獲取行
+    pub fn y(self) -> i32 {
+        self.0 as i32 / 9
+    }
+
+    /// 獲取座標
+    pub fn pos(self) -> (i32, i32) {
+        (self.x(), self.y())
+    }
+
+    /// 遍歷棋盤上所有位置
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..90).map(Square)
+    }
+}
+
+impl TryFrom<(i32, i32)> for Square {
+    type Error = InvalidSquare;
+
+    fn try_from(pos: (i32, i32)) -> Result<Square, InvalidSquare> {
+        Square::new(pos.0, pos.1).ok_or(InvalidSquare(pos.0, pos.1))
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x(), self.y())
+    }
+}
+
+impl From<Square> for (i32, i32) {
+    fn from(square: Square) -> (i32, i32) {
+        square.pos()
+    }
+}
+
+/// 著法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    /// 被吃之棋子，由走法生成填寫
+    pub captured: Option<Piece>,
+    pub flags: u8,
+}
+
+impl Move {
+    /// 帥吃對面之帥
+    pub const FLYING_GENERAL: u8 = 1;
+
+    /// 構建不含吃子信息之著法
+    pub fn new(from: Square, to: Square) -> Move {
+        Move {
+            from,
+            to,
+            captured: None,
+            flags: 0,
+        }
+    }
+
+    /// 是否吃子
+    pub fn is_capture(&self) -> bool {
+        self.captured.is_some()
+    }
+
+    /// 是否與另一著法起止位置相同
+    pub fn same_squares(&self, other: &Move) -> bool {
+        self.from == other.from && self.to == other.to
+    }
+}
+
+impl TryFrom<((i32, i32), (i32, i32))> for Move {
+    type Error = InvalidSquare;
+
+    fn try_from(step: ((i32, i32), (i32, i32))) -> Result<Move, InvalidSquare> {
+        Ok(Move::new(
+            Square::try_from(step.0)?,
+            Square::try_from(step.1)?,
+        ))
+    }
+}
+
+impl From<Move> for ((i32, i32), (i32, i32)) {
+    fn from(step: Move) -> ((i32, i32), (i32, i32)) {
+        (step.from.pos(), step.to.pos())
+    }
+}
+
+/// 一方著法數目之上限
+pub const MAX_MOVES: usize = 128;
+
+/// 棧上分配之著法列表
+#[derive(Clone, Copy)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveList {
+    /// 構建空列表
+    pub fn new() -> MoveList {
+        MoveList {
+            moves: [Move::new(Square(0), Square(0)); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    /// 加入一著
+    pub fn push(&mut self, step: Move) {
+        self.moves[self.len] = step;
+        self.len += 1;
+    }
+
+    /// 清空列表
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl std::ops::Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl std::ops::DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = std::iter::Take<std::array::IntoIter<Move, MAX_MOVES>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.into_iter().take(self.len)
+    }
+}
@@ -0,0 +1,679 @@
+use crate::game::*;
+use crate::moves::*;
+use std::fmt;
+
+/// 記譜解析錯誤
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    /// 格式無法識別
+    Malformed(String),
+    /// 棋盤上無與之對應之合法著法
+    NoMatchingMove(String),
+    /// 棋盤上有多個與之對應之合法著法
+    Ambiguous(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::Malformed(text) => write!(f, "無法識別之記譜 '{}'", text),
+            NotationError::NoMatchingMove(text) => write!(f, "記譜 '{}' 無對應之合法著法", text),
+            NotationError::Ambiguous(text) => write!(f, "記譜 '{}' 有多個對應之著法", text),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+impl Square {
+    /// 自 ICCS 座標（如 h2）構建，列 a–i，行 0–9 自紅方底線起算
+    pub fn from_iccs(text: &str) -> Result<Square, NotationError> {
+        let malformed = || NotationError::Malformed(text.to_string());
+        let mut chars = text.chars();
+        let (file, rank) = match (chars.next(), chars.next(), chars.next()) {
+            (Some(file), Some(rank), None) => (file.to_ascii_lowercase(), rank),
+            _ => return Err(malformed()),
+        };
+        if !('a'..='i').contains(&file) {
+            return Err(malformed());
+        }
+        let rank = rank.to_digit(10).ok_or_else(malformed)?;
+        Square::new(file as i32 - 'a' as i32, rank as i32).ok_or_else(malformed)
+    }
+
+    /// 輸出 ICCS 座標
+    pub fn to_iccs(self) -> String {
+        format!("{}{}", (b'a' + self.x() as u8) as char, self.y())
+    }
+}
+
+impl Move {
+    /// 自 ICCS 著法（如 h2e2，亦接受 H2-E2）構建，不含吃子信息
+    pub fn from_iccs(text: &str) -> Result<Move, NotationError> {
+        let compact: String = text.trim().chars().filter(|c| *c != '-').collect();
+        if compact.len() != 4 || !compact.is_ascii() {
+            return Err(NotationError::Malformed(text.to_string()));
+        }
+        let from = Square::from_iccs(&compact[0..2])
+            .map_err(|_| NotationError::Malformed(text.to_string()))?;
+        let to = Square::from_iccs(&compact[2..4])
+            .map_err(|_| NotationError::Malformed(text.to_string()))?;
+        Ok(Move::new(from, to))
+    }
+
+    /// 輸出 ICCS 著法
+    pub fn to_iccs(&self) -> String {
+        format!("{}{}", self.from.to_iccs(), self.to.to_iccs())
+    }
+}
+
+/// 縱線記譜之動作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    /// 進
+    Advance,
+    /// 退
+    Retreat,
+    /// 平
+    Traverse,
+}
+
+/// 同一縱線上有多個同種棋子時之位置標記
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prefix {
+    /// 前
+    Front,
+    /// 中（僅用於三子同線）
+    Middle,
+    /// 後
+    Rear,
+    /// 自前往後第幾個（用於四子以上同線）
+    Nth(usize),
+}
+
+impl Prefix {
+    /// 同線共 count 子時，自前往後之序號
+    fn index(self, count: usize) -> Option<usize> {
+        match self {
+            Prefix::Front => Some(0),
+            Prefix::Middle if count == 3 => Some(1),
+            Prefix::Middle => None,
+            Prefix::Rear => Some(count - 1),
+            Prefix::Nth(n) if n >= 1 && n <= count => Some(n - 1),
+            Prefix::Nth(_) => None,
+        }
+    }
+
+    /// 同線共 count 子時，第 index 個（自前往後）之標記
+    fn of(index: usize, count: usize) -> Prefix {
+        if count >= 4 {
+            Prefix::Nth(index + 1)
+        } else if index == 0 {
+            Prefix::Front
+        } else if index == count - 1 {
+            Prefix::Rear
+        } else {
+            Prefix::Middle
+        }
+    }
+}
+
+/// 縱線記譜之要素，與具體書寫方式無關
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Description {
+    piece: Piece,
+    /// 同線多子時之位置標記
+    prefix: Option<Prefix>,
+    /// 起點縱線號（1–9，自走子方右手起算），同線多子時可省略
+    file: Option<i32>,
+    action: Action,
+    /// 進退之步數，或平、斜行棋子之終點縱線號
+    number: i32,
+}
+
+/// 某方視角之縱線號（1–9，自該方右手起算）
+fn file_of(side: Side, x: i32) -> i32 {
+    match side {
+        Side::Red => 9 - x,
+        Side::Black => x + 1,
+    }
+}
+
+/// 某方視角之縱線號對應之列
+fn x_of(side: Side, file: i32) -> i32 {
+    match side {
+        Side::Red => 9 - file,
+        Side::Black => file - 1,
+    }
+}
+
+/// 某方之前進方向
+fn forward(side: Side) -> i32 {
+    match side {
+        Side::Red => 1,
+        Side::Black => -1,
+    }
+}
+
+/// 是否以步數記進退（否則以終點縱線號記）
+fn moves_straight(piece: Piece) -> bool {
+    matches!(piece, Piece::兵 | Piece::炮 | Piece::車 | Piece::帥)
+}
+
+/// 某列上 side 方之 piece，自前往後排列
+fn pieces_on_file(board: &Board, side: Side, piece: Piece, x: i32) -> Vec<Square> {
+    let mut squares: Vec<Square> = (0..10)
+        .filter_map(|y| Square::new(x, y))
+        .filter(|square| board.piece_at(*square) == Some((side, piece)))
+        .collect();
+    squares.sort_by_key(|square| -square.y() * forward(side));
+    squares
+}
+
+/// 有兩個以上 side 方 piece 之列
+fn tandem_files(board: &Board, side: Side, piece: Piece) -> Vec<i32> {
+    (0..9)
+        .filter(|x| pieces_on_file(board, side, piece, *x).len() >= 2)
+        .collect()
+}
+
+/// 將著法拆解爲縱線記譜之要素
+fn describe(board: &Board, step: Move) -> Result<Description, NotationError> {
+    let (side, piece) = board
+        .piece_at(step.from)
+        .ok_or_else(|| NotationError::NoMatchingMove(step.to_iccs()))?;
+    let dy = (step.to.y() - step.from.y()) * forward(side);
+    let action = match dy {
+        dy if dy > 0 => Action::Advance,
+        dy if dy < 0 => Action::Retreat,
+        _ => Action::Traverse,
+    };
+    let number = if moves_straight(piece) && action != Action::Traverse {
+        dy.abs()
+    } else {
+        file_of(side, step.to.x())
+    };
+    let file = file_of(side, step.from.x());
+    // 仕、相同線時可由進退區分，不用前後
+    if piece == Piece::仕 || piece == Piece::相 {
+        return Ok(Description {
+            piece,
+            prefix: None,
+            file: Some(file),
+            action,
+            number,
+        });
+    }
+    let same_file = pieces_on_file(board, side, piece, step.from.x());
+    if same_file.len() < 2 {
+        return Ok(Description {
+            piece,
+            prefix: None,
+            file: Some(file),
+            action,
+            number,
+        });
+    }
+    let index = same_file
+        .iter()
+        .position(|square| *square == step.from)
+        .unwrap();
+    // 兵在兩條以上縱線均有多子時保留縱線號
+    let keep_file = piece == Piece::兵 && tandem_files(board, side, piece).len() > 1;
+    Ok(Description {
+        piece,
+        prefix: Some(Prefix::of(index, same_file.len())),
+        file: if keep_file { Some(file) } else { None },
+        action,
+        number,
+    })
+}
+
+/// 依走法求某子按記譜所到之終點
+fn destination(
+    side: Side,
+    piece: Piece,
+    from: Square,
+    action: Action,
+    number: i32,
+) -> Option<Square> {
+    let direction = match action {
+        Action::Advance => forward(side),
+        Action::Retreat => -forward(side),
+        Action::Traverse => 0,
+    };
+    if moves_straight(piece) {
+        match action {
+            Action::Traverse => Square::new(x_of(side, number), from.y()),
+            _ => Square::new(from.x(), from.y() + direction * number),
+        }
+    } else {
+        if action == Action::Traverse {
+            return None;
+        }
+        let dx = (x_of(side, number) - from.x()).abs();
+        let dy = match (piece, dx) {
+            (Piece::馬, 1) => 2,
+            (Piece::馬, 2) => 1,
+            (Piece::相, 2) => 2,
+            (Piece::仕, 1) => 1,
+            _ => return None,
+        };
+        Square::new(x_of(side, number), from.y() + direction * dy)
+    }
+}
+
+/// 依記譜要素於棋盤上找出唯一之合法著法
+fn resolve(board: &Board, description: Description, text: &str) -> Result<Move, NotationError> {
+    let side = board.side_to_move();
+    let piece = description.piece;
+    let mut candidates = Vec::new();
+    match description.prefix {
+        None => {
+            let file = description
+                .file
+                .ok_or_else(|| NotationError::Malformed(text.to_string()))?;
+            candidates.extend(pieces_on_file(board, side, piece, x_of(side, file)));
+        }
+        Some(prefix) => {
+            let files = match description.file {
+                Some(file) => vec![x_of(side, file)],
+                None => tandem_files(board, side, piece),
+            };
+            for x in files {
+                let same_file = pieces_on_file(board, side, piece, x);
+                if let Some(index) = prefix.index(same_file.len()) {
+                    candidates.push(same_file[index]);
+                }
+            }
+        }
+    }
+    let mut found = Vec::new();
+    for from in candidates {
+        let to = match destination(side, piece, from, description.action, description.number) {
+            Some(to) => to,
+            None => continue,
+        };
+        found.extend(
+            board
+                .all_possible_moves(from)
+                .into_iter()
+                .filter(|step| step.to == to),
+        );
+    }
+    match found.len() {
+        0 => Err(NotationError::NoMatchingMove(text.to_string())),
+        1 => Ok(found[0]),
+        _ => Err(NotationError::Ambiguous(text.to_string())),
+    }
+}
+
+const CHINESE_NUMERALS: [char; 9] = ['一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+/// 中文記譜之數字：紅方用中文數字，黑方用阿拉伯數字
+fn chinese_number(side: Side, number: i32) -> char {
+    debug_assert!((1..=9).contains(&number));
+    match side {
+        Side::Red => CHINESE_NUMERALS[number as usize - 1],
+        Side::Black => (b'0' + number as u8) as char,
+    }
+}
+
+/// 解析中文記譜之數字，兼容中文、阿拉伯及全角數字
+fn parse_chinese_number(c: char) -> Option<i32> {
+    if let Some(i) = CHINESE_NUMERALS.iter().position(|numeral| *numeral == c) {
+        return Some(i as i32 + 1);
+    }
+    let c = match c {
+        '１'..='９' => char::from_u32(c as u32 - '１' as u32 + '1' as u32)?,
+        _ => c,
+    };
+    match c.to_digit(10) {
+        Some(n) if n >= 1 => Some(n as i32),
+        _ => None,
+    }
+}
+
+/// 中文記譜之棋子名
+fn chinese_piece(piece: Piece) -> char {
+    match piece {
+        Piece::兵 => '兵',
+        Piece::仕 => '仕',
+        Piece::相 => '相',
+        Piece::炮 => '炮',
+        Piece::馬 => '馬',
+        Piece::車 => '車',
+        Piece::帥 => '帥',
+    }
+}
+
+/// 解析中文記譜之棋子名，兼容黑方及簡體寫法
+fn parse_chinese_piece(c: char) -> Option<Piece> {
+    match c {
+        '兵' | '卒' => Some(Piece::兵),
+        '仕' | '士' => Some(Piece::仕),
+        '相' | '象' => Some(Piece::相),
+        '炮' | '砲' | '包' => Some(Piece::炮),
+        '馬' | '马' | '傌' => Some(Piece::馬),
+        '車' | '车' | '俥' => Some(Piece::車),
+        '帥' | '帅' | '將' | '将' => Some(Piece::帥),
+        _ => None,
+    }
+}
+
+impl Move {
+    /// 以中文縱線記譜（如 炮二平五、馬8進7）表示本著
+    pub fn to_chinese(&self, board: &Board) -> Result<String, NotationError> {
+        let description = describe(board, *self)?;
+        let side = board.piece_at(self.from).unwrap().0;
+        let mut text = String::new();
+        if let Some(prefix) = description.prefix {
+            text.push(match prefix {
+                Prefix::Front => '前',
+                Prefix::Middle => '中',
+                Prefix::Rear => '後',
+                Prefix::Nth(n) => CHINESE_NUMERALS[n - 1],
+            });
+        }
+        match (description.prefix, description.file) {
+            (Some(_), Some(file)) => text.push(chinese_number(side, file)),
+            (Some(_), None) => text.push(chinese_piece(description.piece)),
+            (None, file) => {
+                text.push(chinese_piece(description.piece));
+                text.push(chinese_number(side, file.unwrap()));
+            }
+        }
+        text.push(match description.action {
+            Action::Advance => '進',
+            Action::Retreat => '退',
+            Action::Traverse => '平',
+        });
+        text.push(chinese_number(side, description.number));
+        Ok(text)
+    }
+
+    /// 自中文縱線記譜解析當前走子方之著法
+    pub fn from_chinese(board: &Board, text: &str) -> Result<Move, NotationError> {
+        let malformed = || NotationError::Malformed(text.to_string());
+        let chars: Vec<char> = text.trim().chars().collect();
+        if chars.len() != 4 {
+            return Err(malformed());
+        }
+        let prefix = match chars[0] {
+            '前' => Some(Prefix::Front),
+            '中' => Some(Prefix::Middle),
+            '後' | '后' => Some(Prefix::Rear),
+            c if parse_chinese_piece(chars[1]).is_some() => Some(Prefix::Nth(
+                parse_chinese_number(c).ok_or_else(malformed)? as usize,
+            )),
+            _ => None,
+        };
+        let (piece, file) = match prefix {
+            None => (
+                parse_chinese_piece(chars[0]).ok_or_else(malformed)?,
+                Some(parse_chinese_number(chars[1]).ok_or_else(malformed)?),
+            ),
+            Some(_) => match parse_chinese_piece(chars[1]) {
+                Some(piece) => (piece, None),
+                None => (
+                    Piece::兵,
+                    Some(parse_chinese_number(chars[1]).ok_or_else(malformed)?),
+                ),
+            },
+        };
+        let action = match chars[2] {
+            '進' | '进' => Action::Advance,
+            '退' => Action::Retreat,
+            '平' => Action::Traverse,
+            _ => return Err(malformed()),
+        };
+        let number = parse_chinese_number(chars[3]).ok_or_else(malformed)?;
+        let description = Description {
+            piece,
+            prefix,
+            file,
+            action,
+            number,
+        };
+        resolve(board, description, text)
+    }
+}
+
+/// WXF 記譜之棋子字母
+fn wxf_piece(piece: Piece) -> char {
+    match piece {
+        Piece::兵 => 'P',
+        Piece::仕 => 'A',
+        Piece::相 => 'E',
+        Piece::炮 => 'C',
+        Piece::馬 => 'H',
+        Piece::車 => 'R',
+        Piece::帥 => 'K',
+    }
+}
+
+/// 解析 WXF 記譜之棋子字母，兼容以 B、N 表示相、馬之寫法
+fn parse_wxf_piece(c: char) -> Option<Piece> {
+    match c.to_ascii_uppercase() {
+        'P' => Some(Piece::兵),
+        'A' => Some(Piece::仕),
+        'E' | 'B' => Some(Piece::相),
+        'C' => Some(Piece::炮),
+        'H' | 'N' => Some(Piece::馬),
+        'R' => Some(Piece::車),
+        'K' => Some(Piece::帥),
+        _ => None,
+    }
+}
+
+/// 解析 WXF 記譜之數字
+fn parse_wxf_number(c: Option<char>) -> Option<i32> {
+    match c?.to_digit(10) {
+        Some(n) if n >= 1 => Some(n as i32),
+        _ => None,
+    }
+}
+
+impl Move {
+    /// 以 WXF 記譜（如 C2.5、H8+7、+P.4）表示本著
+    ///
+    /// 同線多子時，二子以 +、- 分前後，三子以 +、=、- 分前中後，四子以上以 1–5 自前往後編號。
+    pub fn to_wxf(&self, board: &Board) -> Result<String, NotationError> {
+        let description = describe(board, *self)?;
+        let mut text = String::new();
+        if let Some(prefix) = description.prefix {
+            text.push(match prefix {
+                Prefix::Front => '+',
+                Prefix::Middle => '=',
+                Prefix::Rear => '-',
+                Prefix::Nth(n) => (b'0' + n as u8) as char,
+            });
+        }
+        text.push(wxf_piece(description.piece));
+        if let Some(file) = description.file {
+            text.push_str(&file.to_string());
+        }
+        text.push(match description.action {
+            Action::Advance => '+',
+            Action::Retreat => '-',
+            Action::Traverse => '.',
+        });
+        text.push_str(&description.number.to_string());
+        Ok(text)
+    }
+
+    /// 自 WXF 記譜解析當前走子方之著法
+    pub fn from_wxf(board: &Board, text: &str) -> Result<Move, NotationError> {
+        let malformed = || NotationError::Malformed(text.to_string());
+        let chars: Vec<char> = text.trim().chars().collect();
+        let mut i = 0;
+        let prefix = match (chars.first(), chars.get(1)) {
+            (Some(c), Some(next)) if parse_wxf_piece(*next).is_some() => {
+                i = 1;
+                Some(match c {
+                    '+' => Prefix::Front,
+                    '=' => Prefix::Middle,
+                    '-' => Prefix::Rear,
+                    c => Prefix::Nth(parse_wxf_number(Some(*c)).ok_or_else(malformed)? as usize),
+                })
+            }
+            _ => None,
+        };
+        let piece = chars
+            .get(i)
+            .and_then(|c| parse_wxf_piece(*c))
+            .ok_or_else(malformed)?;
+        i += 1;
+        let file = parse_wxf_number(chars.get(i).copied());
+        if file.is_some() {
+            i += 1;
+        }
+        let action = match chars.get(i) {
+            Some('+') => Action::Advance,
+            Some('-') => Action::Retreat,
+            Some('.') | Some('=') => Action::Traverse,
+            _ => return Err(malformed()),
+        };
+        let number = parse_wxf_number(chars.get(i + 1).copied()).ok_or_else(malformed)?;
+        if chars.len() != i + 2 {
+            return Err(malformed());
+        }
+        let description = Description {
+            piece,
+            prefix,
+            file,
+            action,
+            number,
+        };
+        resolve(board, description, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 紅車雙疊
+    const TANDEM_CHARIOTS: &str = "4k4/9/9/9/9/R8/9/9/R8/3K5 w - - 0 1";
+    /// 紅兵三子同線
+    const THREE_PAWNS: &str = "4k4/9/2P6/2P6/2P6/9/9/9/9/3K5 w - - 0 1";
+    /// 紅兵四子同線
+    const FOUR_PAWNS: &str = "4k4/2P6/2P6/2P6/2P6/9/9/9/9/3K5 w - - 0 1";
+    /// 紅兵兩條縱線各兩子
+    const PAWN_PAIRS: &str = "4k4/9/9/2P3P2/2P3P2/9/9/9/9/3K5 w - - 0 1";
+    /// 黑車雙疊
+    const BLACK_TANDEM_CHARIOTS: &str = "4k3r/9/9/8r/9/9/9/9/9/3K5 b - - 0 1";
+    /// 紅兵中路雙疊
+    const CENTRAL_PAWNS: &str = "4k4/9/9/4P4/4P4/9/9/9/9/3K5 w - - 0 1";
+
+    const ROUND_TRIP_POSITIONS: [&str; 9] = [
+        START_FEN,
+        CENTRAL_PAWNS,
+        TANDEM_CHARIOTS,
+        THREE_PAWNS,
+        FOUR_PAWNS,
+        PAWN_PAIRS,
+        BLACK_TANDEM_CHARIOTS,
+        "1C2ka3/9/C1Nab1n2/p3p3p/6p2/9/P3P3P/3AB4/3p2c2/c1BAK4 w - - 0 1",
+        "1rbaka2R/5r3/6n2/2p1p1p2/4P1bP1/PpC3Bc1/1nPR2P2/2N2AN2/1c2K1p2/2BAC4 w - - 0 1",
+    ];
+
+    // 檢查 fen 局面下 ICCS 著法與中文記譜互相對應
+    fn assert_chinese(fen: &str, iccs: &str, chinese: &str) {
+        let board = Board::from_fen(fen).unwrap();
+        let step = Move::from_iccs(iccs).unwrap();
+        assert_eq!(step.to_chinese(&board).unwrap(), chinese, "{}", fen);
+        assert!(Move::from_chinese(&board, chinese)
+            .unwrap()
+            .same_squares(&step));
+    }
+
+    #[test]
+    fn chinese_basic_moves() {
+        assert_chinese(START_FEN, "h2e2", "炮二平五");
+        let mut board = Board::new();
+        board.do_move(Move::from_iccs("h2e2").unwrap()).unwrap();
+        assert_chinese(&board.to_fen(), "h9g7", "馬8進7");
+        // 兼容簡體及黑方用字
+        let step = Move::from_chinese(&board, "马8进7").unwrap();
+        assert!(step.same_squares(&Move::from_iccs("h9g7").unwrap()));
+    }
+
+    #[test]
+    fn chinese_tandem_pieces() {
+        assert_chinese(TANDEM_CHARIOTS, "a4a6", "前車進二");
+        assert_chinese(TANDEM_CHARIOTS, "a1a2", "後車進一");
+        assert_chinese(TANDEM_CHARIOTS, "a4d4", "前車平六");
+        assert_chinese(THREE_PAWNS, "c7c8", "前兵進一");
+        assert_chinese(THREE_PAWNS, "c6b6", "中兵平八");
+        assert_chinese(THREE_PAWNS, "c5d5", "後兵平六");
+        assert_chinese(FOUR_PAWNS, "c7b7", "二兵平八");
+        assert_chinese(FOUR_PAWNS, "c5d5", "四兵平六");
+        // 兩條縱線均有兵疊時保留縱線號
+        assert_chinese(PAWN_PAIRS, "c6c7", "前七進一");
+        assert_chinese(PAWN_PAIRS, "g5f5", "後三平四");
+        // 黑方用阿拉伯數字，前後依黑方視角
+        assert_chinese(BLACK_TANDEM_CHARIOTS, "i6i4", "前車進2");
+        assert_chinese(BLACK_TANDEM_CHARIOTS, "i9h9", "後車平8");
+    }
+
+    #[test]
+    fn chinese_round_trip() {
+        for fen in ROUND_TRIP_POSITIONS {
+            let board = Board::from_fen(fen).unwrap();
+            for step in board.legal_moves(board.side_to_move()) {
+                let text = step.to_chinese(&board).unwrap();
+                let parsed = Move::from_chinese(&board, &text).unwrap();
+                assert!(parsed.same_squares(&step), "{} {}", fen, text);
+            }
+        }
+    }
+
+    // 檢查 fen 局面下 ICCS 著法與 WXF 記譜互相對應
+    fn assert_wxf(fen: &str, iccs: &str, wxf: &str) {
+        let board = Board::from_fen(fen).unwrap();
+        let step = Move::from_iccs(iccs).unwrap();
+        assert_eq!(step.to_wxf(&board).unwrap(), wxf, "{}", fen);
+        assert!(Move::from_wxf(&board, wxf).unwrap().same_squares(&step));
+    }
+
+    #[test]
+    fn wxf_basic_moves() {
+        assert_wxf(START_FEN, "h2e2", "C2.5");
+        let mut board = Board::new();
+        board.do_move(Move::from_iccs("h2e2").unwrap()).unwrap();
+        assert_wxf(&board.to_fen(), "h9g7", "H8+7");
+        // 兼容以 N 表示馬、以 = 表示平
+        let step = Move::from_wxf(&board, "N8+7").unwrap();
+        assert!(step.same_squares(&Move::from_iccs("h9g7").unwrap()));
+        let step = Move::from_wxf(&Board::new(), "C2=5").unwrap();
+        assert!(step.same_squares(&Move::from_iccs("h2e2").unwrap()));
+        assert!(matches!(
+            Move::from_wxf(&Board::new(), "C2.5x"),
+            Err(NotationError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn wxf_tandem_pieces() {
+        assert_wxf(CENTRAL_PAWNS, "e6f6", "+P.4");
+        assert_wxf(CENTRAL_PAWNS, "e5d5", "-P.6");
+        assert_wxf(TANDEM_CHARIOTS, "a4a6", "+R+2");
+        assert_wxf(THREE_PAWNS, "c6b6", "=P.8");
+        assert_wxf(FOUR_PAWNS, "c7b7", "2P.8");
+        assert_wxf(PAWN_PAIRS, "c6c7", "+P7+1");
+        assert_wxf(BLACK_TANDEM_CHARIOTS, "i9h9", "-R.8");
+    }
+
+    #[test]
+    fn wxf_round_trip() {
+        for fen in ROUND_TRIP_POSITIONS {
+            let board = Board::from_fen(fen).unwrap();
+            for step in board.legal_moves(board.side_to_move()) {
+                let text = step.to_wxf(&board).unwrap();
+                let parsed = Move::from_wxf(&board, &text).unwrap();
+                assert!(parsed.same_squares(&step), "{} {}", fen, text);
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use crate::ai::*;
+use crate::game::*;
+use crate::weights::*;
+use std::path::Path;
+
+/// 子力位置表估價器——子力價值加位置分，按局面階段在開中局與殘局之間插值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PstEvaluator {
+    weights: EvalWeights,
+}
+
+impl Default for PstEvaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PstEvaluator {
+    /// 以默認權重構建
+    pub fn new() -> Self {
+        Self::with_weights(EvalWeights::default())
+    }
+
+    /// 以給定權重構建
+    pub fn with_weights(weights: EvalWeights) -> Self {
+        Self { weights }
+    }
+
+    /// 以權重文件構建
+    pub fn from_file(path: &Path) -> Result<Self, WeightsError> {
+        Ok(Self::with_weights(EvalWeights::load(path)?))
+    }
+
+    /// 獲取權重
+    pub fn weights(&self) -> &EvalWeights {
+        &self.weights
+    }
+
+    /// 某方棋子在 (x, y) 處之開中局、殘局分數（黑方上下左右翻轉後查表）
+    pub fn piece_score(&self, piece: (Side, Piece), x: usize, y: usize) -> (i32, i32) {
+        let (row, col) = match piece.0 {
+            Side::Red => (9 - y, x),
+            Side::Black => (y, 8 - x),
+        };
+        let weights = self.weights.piece(piece.1);
+        (
+            weights.material_mg + weights.mg[row][col],
+            weights.material_eg + weights.eg[row][col],
+        )
+    }
+}
+
+impl Evaluator for PstEvaluator {
+    fn evaluate(&self, board: &Board, side: Side) -> i32 {
+        let mut mg = 0;
+        let mut eg = 0;
+        let mut phase = 0;
+        for x in 0..9 {
+            for y in 0..10 {
+                let piece = match board.get_map()[x][y] {
+                    Some(piece) => piece,
+                    None => continue,
+                };
+                let (piece_mg, piece_eg) = self.piece_score(piece, x, y);
+                if piece.0 == side {
+                    mg += piece_mg;
+                    eg += piece_eg;
+                } else {
+                    mg -= piece_mg;
+                    eg -= piece_eg;
+                }
+                phase += self.weights.piece(piece.1).phase;
+            }
+        }
+        let phase_max = self.weights.phase_max;
+        let phase = phase.min(phase_max);
+        (mg * phase + eg * (phase_max - phase)) / phase_max
+    }
+}
@@ -0,0 +1,63 @@
+/// 重複局面之裁決方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepetitionRule {
+    /// 亞洲規則：長將者負；雙方均不長將時，長捉者負；其餘判和
+    Asian,
+    /// 中國規則：長將者負；此外將、捉交替亦屬長打，長打者負；其餘判和
+    Chinese,
+    /// 一律判和
+    AlwaysDraw,
+}
+
+/// 對局規則
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    /// 重複局面之裁決方式
+    pub repetition: RepetitionRule,
+    /// 同一局面出現此次數時按重複局面裁決
+    pub repetition_count: usize,
+    /// 困斃是否判負，否則判和
+    pub stalemate_loses: bool,
+    /// 自然限著：連續如此多步未吃子即判和，爲 None 則不限
+    pub halfmove_limit: Option<u32>,
+    /// 能否以帥吃帥：是則帥帥照面不算將軍，走成照面之一方將被對方之帥吃去；
+    /// 否則照面視同將軍，不得走成
+    pub flying_general_capture: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::asian()
+    }
+}
+
+impl Rules {
+    /// 亞洲象棋聯合會規則
+    pub fn asian() -> Self {
+        Self {
+            repetition: RepetitionRule::Asian,
+            repetition_count: 3,
+            stalemate_loses: true,
+            halfmove_limit: Some(120),
+            flying_general_capture: false,
+        }
+    }
+
+    /// 中國象棋協會競賽規則
+    pub fn chinese() -> Self {
+        Self {
+            repetition: RepetitionRule::Chinese,
+            ..Self::asian()
+        }
+    }
+
+    /// 娛樂規則：重複局面一律判和，不限著，照面時可以帥吃帥
+    pub fn casual() -> Self {
+        Self {
+            repetition: RepetitionRule::AlwaysDraw,
+            halfmove_limit: None,
+            flying_general_capture: true,
+            ..Self::asian()
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::ai::*;
+use crate::moves::*;
+
+/// 置換表默認大小（MB）
+pub const DEFAULT_TT_SIZE_MB: usize = 16;
+
+/// 分數之界限類型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// 精確值
+    Exact,
+    /// 下界（曾發生 beta 截斷）
+    Lower,
+    /// 上界（無著法超過 alpha）
+    Upper,
+}
+
+/// 置換表項
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TTEntry {
+    /// 局面之 Zobrist 鍵
+    pub key: u64,
+    /// 搜索深度
+    pub depth: u32,
+    pub bound: Bound,
+    /// 以走子方視角之分數，將死分數按距本局面之步數記錄
+    pub score: i32,
+    pub best_move: Option<Move>,
+}
+
+/// 固定大小之置換表，以局面鍵之低位爲下標
+#[derive(Debug, Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Option<TTEntry>>,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_TT_SIZE_MB)
+    }
+}
+
+impl TranspositionTable {
+    /// 以約 megabytes MB 內存構建，項數取不超過該大小之二的冪
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let bytes = megabytes.max(1) * 1024 * 1024;
+        let capacity = bytes / std::mem::size_of::<Option<TTEntry>>();
+        let capacity = 1 << (usize::BITS - 1 - capacity.max(1).leading_zeros());
+        TranspositionTable {
+            entries: vec![None; capacity],
+        }
+    }
+
+    /// 項數
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 清空
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    // 某鍵所在之下標
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    /// 查詢局面，ply 爲該局面距根節點之步數，返回之將死分數已換算爲距根節點
+    pub fn probe(&self, key: u64, ply: u32) -> Option<TTEntry> {
+        let mut entry = self.entries[self.index(key)].filter(|entry| entry.key == key)?;
+        entry.score = score_from_tt(entry.score, ply);
+        Some(entry)
+    }
+
+    /// 記錄局面；同一局面或較淺之舊項方被替換，其他局面則總是替換
+    pub fn store(
+        &mut self,
+        key: u64,
+        ply: u32,
+        depth: u32,
+        bound: Bound,
+        score: i32,
+        best_move: Option<Move>,
+    ) {
+        let index = self.index(key);
+        if let Some(old) = self.entries[index] {
+            if old.key == key && old.depth > depth {
+                return;
+            }
+        }
+        self.entries[index] = Some(TTEntry {
+            key,
+            depth,
+            bound,
+            score: score_to_tt(score, ply),
+            best_move,
+        });
+    }
+}
+
+// 將死分數由距根節點改爲距本局面
+fn score_to_tt(score: i32, ply: u32) -> i32 {
+    if score > MATE_BOUND {
+        score + ply as i32
+    } else if score < -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+// 將死分數由距本局面改爲距根節點
+fn score_from_tt(score: i32, ply: u32) -> i32 {
+    if score > MATE_BOUND {
+        score - ply as i32
+    } else if score < -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mate_scores_keep_distance_across_plies() {
+        let mut tt = TranspositionTable::new(1);
+        // 於第 3 步之局面記錄：自根節點第 7 步將死對方，即距本局面 4 步
+        tt.store(1, 3, 4, Bound::Exact, MATE_SCORE - 7, None);
+        assert_eq!(tt.probe(1, 3).unwrap().score, MATE_SCORE - 7);
+        assert_eq!(tt.probe(1, 5).unwrap().score, MATE_SCORE - 9);
+        assert_eq!(tt.probe(1, 1).unwrap().score, MATE_SCORE - 5);
+
+        tt.store(2, 3, 4, Bound::Exact, -(MATE_SCORE - 7), None);
+        assert_eq!(tt.probe(2, 5).unwrap().score, -(MATE_SCORE - 9));
+        assert_eq!(tt.probe(2, 1).unwrap().score, -(MATE_SCORE - 5));
+
+        // 非將死分數不隨步數改變
+        tt.store(3, 3, 4, Bound::Lower, 250, None);
+        assert_eq!(tt.probe(3, 5).unwrap().score, 250);
+        assert_eq!(tt.probe(4, 5), None);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::ai::*;
+use crate::game::*;
+use crate::pst::*;
+use crate::weights::*;
+use std::fmt;
+use std::path::Path;
+
+/// 調參數據集中之一個局面
+#[derive(Debug, Clone)]
+pub struct TuningPosition {
+    pub board: Board,
+    /// 紅方之實際得分：勝 1，和 0.5，負 0
+    pub result: f64,
+}
+
+/// 數據集讀取錯誤
+#[derive(Debug)]
+pub enum DatasetError {
+    /// 無法讀取文件
+    Io(std::io::Error),
+    /// 某行（自 1 起算）無法解析
+    InvalidLine(usize, String),
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::Io(error) => write!(f, "無法讀取數據集：{}", error),
+            DatasetError::InvalidLine(line, text) => {
+                write!(f, "數據集第 {} 行無法解析：{}", line, text)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DatasetError {}
+
+/// 解析對局結果（紅方視角）：1-0、0-1、1/2-1/2 或 1、0、0.5
+fn parse_result(text: &str) -> Option<f64> {
+    match text {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" => Some(0.5),
+        _ => text
+            .parse::<f64>()
+            .ok()
+            .filter(|result| (0.0..=1.0).contains(result)),
+    }
+}
+
+/// 讀取數據集：每行爲「FEN; 結果」，空行及 # 開頭之行忽略
+pub fn load_dataset(path: &Path) -> Result<Vec<TuningPosition>, DatasetError> {
+    let text = std::fs::read_to_string(path).map_err(DatasetError::Io)?;
+    let mut dataset = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || DatasetError::InvalidLine(index + 1, line.to_string());
+        let (fen, result) = line.rsplit_once(';').ok_or_else(invalid)?;
+        let board = Board::from_fen(fen.trim()).map_err(|_| invalid())?;
+        let result = parse_result(result.trim()).ok_or_else(invalid)?;
+        dataset.push(TuningPosition { board, result });
+    }
+    Ok(dataset)
+}
+
+/// 將紅方分數映射爲紅方之預期得分：1 / (1 + 10^(-k·score/400))
+fn expected_result(score: i32, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score as f64 / 400.0))
+}
+
+// 各局面紅方分數之預期得分與實際得分之均方誤差
+fn scores_error(scores: &[i32], dataset: &[TuningPosition], k: f64) -> f64 {
+    if dataset.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = scores
+        .iter()
+        .zip(dataset)
+        .map(|(&score, position)| (position.result - expected_result(score, k)).powi(2))
+        .sum();
+    total / dataset.len() as f64
+}
+
+// 以給定權重估價數據集中各局面（紅方視角）
+fn evaluate_dataset(weights: &EvalWeights, dataset: &[TuningPosition]) -> Vec<i32> {
+    let evaluator = PstEvaluator::with_weights(weights.clone());
+    dataset
+        .iter()
+        .map(|position| evaluator.evaluate(&position.board, Side::Red))
+        .collect()
+}
+
+/// 以給定權重估價，數據集上預期得分與實際得分之均方誤差，k 爲分數之縮放係數
+pub fn mean_squared_error(weights: &EvalWeights, dataset: &[TuningPosition], k: f64) -> f64 {
+    scores_error(&evaluate_dataset(weights, dataset), dataset, k)
+}
+
+/// 擬合縮放係數 k，使給定權重於數據集上之誤差最小：於 0..10 內逐次以十分之一步長細分搜索。
+/// 調參時 k 保持不變，以免權重整體放大或縮小亦能降低誤差
+pub fn fit_scaling(weights: &EvalWeights, dataset: &[TuningPosition]) -> f64 {
+    let scores = evaluate_dataset(weights, dataset);
+    let mut best = 1.0;
+    let (mut start, mut end, mut step) = (0.0, 10.0, 1.0);
+    for _ in 0..5 {
+        let mut best_error = f64::INFINITY;
+        let mut k = start;
+        while k <= end {
+            let error = scores_error(&scores, dataset, k);
+            if error < best_error {
+                best_error = error;
+                best = k;
+            }
+            k += step;
+        }
+        start = (best - step).max(0.0);
+        end = best + step;
+        step /= 10.0;
+    }
+    best
+}
+
+/// Texel 式局部搜索：以縮放係數 k 計算誤差，逐個權重試探加減 1，誤差下降則保留；
+/// 一輪無任何改進或達到 max_rounds 輪即停止，每輪結束後以輪數與誤差調用 report
+pub fn tune(
+    mut weights: EvalWeights,
+    dataset: &[TuningPosition],
+    k: f64,
+    max_rounds: u32,
+    mut report: impl FnMut(u32, f64),
+) -> EvalWeights {
+    let mut best_error = mean_squared_error(&weights, dataset, k);
+    let params = EvalWeights::tunable_params();
+    for round in 1..=max_rounds {
+        let mut improved = false;
+        for &param in &params {
+            for delta in [1, -1] {
+                let mut candidate = weights.clone();
+                candidate.adjust(param, delta);
+                let error = mean_squared_error(&candidate, dataset, k);
+                if error < best_error {
+                    best_error = error;
+                    weights = candidate;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        report(round, best_error);
+        if !improved {
+            break;
+        }
+    }
+    weights
+}
+
+/// 讀取數據集，以初始權重擬合縮放係數，自 weights 起調參，並將結果寫入 output
+pub fn run_tuning(
+    dataset: &Path,
+    output: &Path,
+    weights: EvalWeights,
+    max_rounds: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dataset = load_dataset(dataset)?;
+    let k = fit_scaling(&weights, &dataset);
+    println!(
+        "{} 個局面，縮放係數 {:.4}，初始誤差 {:.6}",
+        dataset.len(),
+        k,
+        mean_squared_error(&weights, &dataset, k)
+    );
+    let weights = tune(weights, &dataset, k, max_rounds, |round, error| {
+        println!("第 {} 輪，誤差 {:.6}", round, error);
+    });
+    weights.save(output)?;
+    println!("已寫入 {}", output.display());
+    Ok(())
+}
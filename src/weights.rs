@@ -0,0 +1,276 @@
+use crate::game::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// 子力位置表：自紅方視角書寫，首行爲黑方底線（y = 9），末行爲紅方底線（y = 0）
+pub type PieceSquareTable = [[i32; 9]; 10];
+
+/// 默認權重文件
+const DEFAULT_WEIGHTS: &str = include_str!("../weights/default.toml");
+
+/// 單種棋子之權重
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PieceWeights {
+    /// 開中局子力價值
+    pub material_mg: i32,
+    /// 殘局子力價值
+    pub material_eg: i32,
+    /// 局面階段權重
+    pub phase: i32,
+    /// 開中局子力位置表
+    pub mg: PieceSquareTable,
+    /// 殘局子力位置表
+    pub eg: PieceSquareTable,
+}
+
+/// 估價權重
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvalWeights {
+    /// 開局時各子階段權重之和
+    pub phase_max: i32,
+    pub pawn: PieceWeights,
+    pub advisor: PieceWeights,
+    pub elephant: PieceWeights,
+    pub cannon: PieceWeights,
+    pub horse: PieceWeights,
+    pub chariot: PieceWeights,
+    pub general: PieceWeights,
+}
+
+/// 可供調參之權重
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TunableParam {
+    /// 開中局子力價值
+    MaterialMg(Piece),
+    /// 殘局子力價值
+    MaterialEg(Piece),
+    /// 位置表之一格，與其左右對稱之格同調
+    Square {
+        piece: Piece,
+        /// 是否爲殘局位置表
+        endgame: bool,
+        row: usize,
+        col: usize,
+    },
+}
+
+/// 位置表第 row 行第 col 列（紅方視角）是否爲該種棋子可到之處
+fn reachable(piece: Piece, row: usize, col: usize) -> bool {
+    let (x, y) = (col, 9 - row);
+    match piece {
+        Piece::帥 => (3..=5).contains(&x) && y <= 2,
+        Piece::仕 => matches!((x, y), (3 | 5, 0) | (4, 1) | (3 | 5, 2)),
+        Piece::相 => matches!((x, y), (2 | 6, 0) | (0 | 4 | 8, 2) | (2 | 6, 4)),
+        // 未過河之兵只能直進，僅在原縱線上
+        Piece::兵 => y >= 5 || (3..=4).contains(&y) && x % 2 == 0,
+        Piece::馬 | Piece::炮 | Piece::車 => true,
+    }
+}
+
+/// 權重文件讀取錯誤
+#[derive(Debug)]
+pub enum WeightsError {
+    /// 無法讀寫文件
+    Io(std::io::Error),
+    /// TOML 格式有誤
+    Toml(toml::de::Error),
+    /// JSON 格式有誤或無法導出
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for WeightsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightsError::Io(error) => write!(f, "無法讀寫權重文件：{}", error),
+            WeightsError::Toml(error) => write!(f, "權重文件 TOML 格式有誤：{}", error),
+            WeightsError::Json(error) => write!(f, "權重文件 JSON 格式有誤：{}", error),
+        }
+    }
+}
+
+impl std::error::Error for WeightsError {}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        Self::from_toml(DEFAULT_WEIGHTS).expect("默認權重文件有誤")
+    }
+}
+
+impl EvalWeights {
+    /// 自 TOML 文本解析
+    pub fn from_toml(text: &str) -> Result<EvalWeights, WeightsError> {
+        toml::from_str(text).map_err(WeightsError::Toml)
+    }
+
+    /// 自 JSON 文本解析
+    pub fn from_json(text: &str) -> Result<EvalWeights, WeightsError> {
+        serde_json::from_str(text).map_err(WeightsError::Json)
+    }
+
+    /// 自文件讀取，擴展名爲 .json 者按 JSON 解析，其餘按 TOML 解析
+    pub fn load(path: &Path) -> Result<EvalWeights, WeightsError> {
+        let text = std::fs::read_to_string(path).map_err(WeightsError::Io)?;
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            Self::from_json(&text)
+        } else {
+            Self::from_toml(&text)
+        }
+    }
+
+    /// 寫入文件，擴展名爲 .json 者寫作 JSON，其餘寫作 TOML
+    pub fn save(&self, path: &Path) -> Result<(), WeightsError> {
+        let text = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::to_string_pretty(self).map_err(WeightsError::Json)?
+        } else {
+            self.to_toml()
+        };
+        std::fs::write(path, text).map_err(WeightsError::Io)
+    }
+
+    /// 導出爲 TOML，位置表按棋盤形狀排列，格式與默認權重文件相同
+    pub fn to_toml(&self) -> String {
+        let mut text = format!("phase_max = {}\n", self.phase_max);
+        for (name, weights) in self.pieces() {
+            text += &format!(
+                "\n[{}]\nmaterial_mg = {}\nmaterial_eg = {}\nphase = {}\n",
+                name, weights.material_mg, weights.material_eg, weights.phase
+            );
+            for (key, table) in [("mg", &weights.mg), ("eg", &weights.eg)] {
+                text += &format!("{} = [\n", key);
+                for row in table {
+                    let cells: Vec<String> =
+                        row.iter().map(|value| format!("{:4}", value)).collect();
+                    text += &format!("    [{}],\n", cells.join(", "));
+                }
+                text += "]\n";
+            }
+        }
+        text
+    }
+
+    /// 可供調參之權重：除帥以外之子力價值，以及位置表中棋子可到之格；
+    /// 左右對稱之兩格合爲一項，只列左半
+    pub fn tunable_params() -> Vec<TunableParam> {
+        let pieces = [
+            Piece::兵,
+            Piece::仕,
+            Piece::相,
+            Piece::炮,
+            Piece::馬,
+            Piece::車,
+            Piece::帥,
+        ];
+        let mut params = Vec::new();
+        for piece in pieces {
+            if piece != Piece::帥 {
+                params.push(TunableParam::MaterialMg(piece));
+                params.push(TunableParam::MaterialEg(piece));
+            }
+            for endgame in [false, true] {
+                for row in 0..10 {
+                    for col in 0..=4 {
+                        if reachable(piece, row, col) {
+                            params.push(TunableParam::Square {
+                                piece,
+                                endgame,
+                                row,
+                                col,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        params
+    }
+
+    /// 將某項權重加上 delta（位置表之格連同其對稱格）
+    pub fn adjust(&mut self, param: TunableParam, delta: i32) {
+        match param {
+            TunableParam::MaterialMg(piece) => self.piece_mut(piece).material_mg += delta,
+            TunableParam::MaterialEg(piece) => self.piece_mut(piece).material_eg += delta,
+            TunableParam::Square {
+                piece,
+                endgame,
+                row,
+                col,
+            } => {
+                let weights = self.piece_mut(piece);
+                let table = if endgame {
+                    &mut weights.eg
+                } else {
+                    &mut weights.mg
+                };
+                table[row][col] += delta;
+                if col != 8 - col {
+                    table[row][8 - col] += delta;
+                }
+            }
+        }
+    }
+
+    // 各棋子之權重及其在文件中之名稱
+    fn pieces(&self) -> [(&'static str, &PieceWeights); 7] {
+        [
+            ("pawn", &self.pawn),
+            ("advisor", &self.advisor),
+            ("elephant", &self.elephant),
+            ("cannon", &self.cannon),
+            ("horse", &self.horse),
+            ("chariot", &self.chariot),
+            ("general", &self.general),
+        ]
+    }
+
+    /// 獲取某種棋子之權重
+    pub fn piece(&self, piece: Piece) -> &PieceWeights {
+        match piece {
+            Piece::兵 => &self.pawn,
+            Piece::仕 => &self.advisor,
+            Piece::相 => &self.elephant,
+            Piece::炮 => &self.cannon,
+            Piece::馬 => &self.horse,
+            Piece::車 => &self.chariot,
+            Piece::帥 => &self.general,
+        }
+    }
+
+    // 獲取某種棋子之可變權重
+    fn piece_mut(&mut self, piece: Piece) -> &mut PieceWeights {
+        match piece {
+            Piece::兵 => &mut self.pawn,
+            Piece::仕 => &mut self.advisor,
+            Piece::相 => &mut self.elephant,
+            Piece::炮 => &mut self.cannon,
+            Piece::馬 => &mut self.horse,
+            Piece::車 => &mut self.chariot,
+            Piece::帥 => &mut self.general,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        // 默認權重文件可解析
+        let default = EvalWeights::from_toml(DEFAULT_WEIGHTS).unwrap();
+        assert_eq!(default, EvalWeights::default());
+        let mut adjusted = default.clone();
+        adjusted.adjust(EvalWeights::tunable_params()[0], 7);
+        for weights in [default, adjusted] {
+            assert_eq!(EvalWeights::from_toml(&weights.to_toml()).unwrap(), weights);
+            let json = serde_json::to_string_pretty(&weights).unwrap();
+            assert_eq!(EvalWeights::from_json(&json).unwrap(), weights);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::game::*;
+
+/// 固定種子之 splitmix64，保證各次運行之鍵相同
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn generate_keys() -> ([[u64; 90]; 14], u64) {
+    let mut keys = [[0u64; 90]; 14];
+    let mut state = 0x6368_656e_666f_6e00;
+    let mut piece = 0;
+    while piece < 14 {
+        let mut square = 0;
+        while square < 90 {
+            let (next, key) = splitmix64(state);
+            state = next;
+            keys[piece][square] = key;
+            square += 1;
+        }
+        piece += 1;
+    }
+    let (_, side_key) = splitmix64(state);
+    (keys, side_key)
+}
+
+const KEYS: ([[u64; 90]; 14], u64) = generate_keys();
+
+/// 某方某棋子在某位置（0..90）之鍵
+pub fn piece_key(piece: (Side, Piece), square: usize) -> u64 {
+    let side_offset = match piece.0 {
+        Side::Red => 0,
+        Side::Black => 7,
+    };
+    KEYS.0[side_offset + piece.1 as usize - 1][square]
+}
+
+/// 黑方走子時附加之鍵
+pub fn side_key() -> u64 {
+    KEYS.1
+}